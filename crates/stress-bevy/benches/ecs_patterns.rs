@@ -0,0 +1,221 @@
+//! Microbenchmarks of the ECS patterns the game leans on, run on a bare `World` with no window,
+//! renderer or physics stepping, so regressions show up at the ECS level.
+
+use bevy::{
+    ecs::system::{CommandQueue, SystemState},
+    prelude::*,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use stress_bevy::{
+    animation::{find_animation_player_entity, hacky_height_fix, HackyHeightFix},
+    archetypes::{MonsterArchetype, MonsterKind, MonsterRegistry, RegisteredArchetype},
+    camera::{look_at_character, GameCamera, LookTransform},
+    character::Character,
+    collision_layers::CollisionMatrix,
+    waves::{spawn_monster, Monster},
+};
+
+/// Monster bodies with the built-in archetype. Models are added by the visuals plugin, which
+/// isn't part of this.
+struct Spawner {
+    layers: CollisionMatrix,
+    registry: MonsterRegistry,
+}
+
+impl Default for Spawner {
+    fn default() -> Self {
+        Self {
+            layers: CollisionMatrix::default(),
+            registry: MonsterRegistry::new(vec![RegisteredArchetype {
+                archetype: MonsterArchetype::default(),
+                scene: Handle::default(),
+                idle: None,
+            }]),
+        }
+    }
+}
+
+impl Spawner {
+    fn spawn(&self, world: &mut World, count: usize) {
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, world);
+        for i in 0..count {
+            spawn_monster(
+                Vec3::new(i as f32, 1., 0.),
+                MonsterKind(0),
+                &mut commands,
+                &self.registry,
+                &self.layers,
+            );
+        }
+        queue.apply(world);
+    }
+}
+
+// A tree `depth` levels deep with `branching` children per node and the player on the last leaf,
+// so the search visits every node, like a glTF scene with its skeleton at the bottom
+fn spawn_hierarchy(world: &mut World, depth: usize, branching: usize) -> Entity {
+    let root = world.spawn().id();
+    let mut level = vec![root];
+
+    for _ in 0..depth {
+        let mut next = Vec::with_capacity(level.len() * branching);
+        for &parent in &level {
+            for _ in 0..branching {
+                let child = world.spawn().id();
+                world.entity_mut(parent).push_children(&[child]);
+                next.push(child);
+            }
+        }
+        level = next;
+    }
+
+    let leaf = *level.last().unwrap();
+    world.entity_mut(leaf).insert(AnimationPlayer::default());
+    root
+}
+
+/// Which transforms `move_transforms` changes each run.
+#[derive(Clone, Copy)]
+struct Moving {
+    character: bool,
+    others: bool,
+}
+
+fn move_transforms(
+    moving: Res<Moving>,
+    mut transforms: Query<(&mut Transform, Option<&Character>)>,
+) {
+    for (mut transform, character) in transforms.iter_mut() {
+        let moves = if character.is_some() {
+            moving.character
+        } else {
+            moving.others
+        };
+        if moves {
+            transform.translation.x += 1.;
+        }
+    }
+}
+
+fn spawn_monster_batches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spawn_monster");
+    for count in [10, 100, 1000] {
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            let spawner = Spawner::default();
+            b.iter_batched_ref(
+                World::new,
+                |world| spawner.spawn(world, count),
+                BatchSize::SmallInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn hacky_height_fix_churn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hacky_height_fix");
+    for count in [100, 1000, 5000] {
+        let spawner = Spawner::default();
+        let mut world = World::new();
+        spawner.spawn(&mut world, count);
+        world.insert_resource(spawner.registry);
+        let monsters: Vec<Entity> = world
+            .query_filtered::<Entity, With<Monster>>()
+            .iter(&world)
+            .collect();
+        let mut stage = SystemStage::single_threaded().with_system(hacky_height_fix);
+
+        // Every monster moves archetype twice per iteration, in on the insert and out on the run
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, _| {
+            b.iter(|| {
+                for &monster in &monsters {
+                    world.entity_mut(monster).insert(HackyHeightFix);
+                }
+                stage.run(&mut world);
+            });
+        });
+    }
+    group.finish();
+}
+
+fn animation_player_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_animation_player_entity");
+    for (depth, branching) in [(4, 2), (8, 2), (4, 4), (16, 1)] {
+        let mut world = World::new();
+        let root = spawn_hierarchy(&mut world, depth, branching);
+        let mut state = SystemState::<(Query<&Children>, Query<&AnimationPlayer>)>::new(&mut world);
+
+        let id = format!("depth {} branching {}", depth, branching);
+        group.bench_function(id, |b| {
+            b.iter(|| {
+                let (children, players) = state.get(&world);
+                find_animation_player_entity(root, &children, &players).unwrap()
+            });
+        });
+    }
+    group.finish();
+}
+
+fn changed_transform_queries(c: &mut Criterion) {
+    let mut group = c.benchmark_group("look_at_character");
+    let count = 5000;
+    for (name, moving) in [
+        (
+            "character moved",
+            Moving {
+                character: true,
+                others: false,
+            },
+        ),
+        (
+            "others moved",
+            Moving {
+                character: false,
+                others: true,
+            },
+        ),
+        (
+            "nothing moved",
+            Moving {
+                character: false,
+                others: false,
+            },
+        ),
+    ] {
+        // One character among many other transforms, like monsters and projectiles
+        let mut world = World::new();
+        world.insert_resource(moving);
+        world
+            .spawn()
+            .insert_bundle(TransformBundle::default())
+            .insert(Character);
+        for _ in 0..count {
+            world.spawn().insert_bundle(TransformBundle::default());
+        }
+        world.spawn().insert_bundle((
+            LookTransform {
+                eye: Vec3::new(-2., 5., 5.),
+                target: Vec3::ZERO,
+            },
+            GameCamera,
+        ));
+        let mut stage = SystemStage::single_threaded()
+            .with_system(move_transforms)
+            .with_system(look_at_character.after(move_transforms));
+
+        group.bench_function(format!("{} of {}", name, count), |b| {
+            b.iter(|| stage.run(&mut world));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    spawn_monster_batches,
+    hacky_height_fix_churn,
+    animation_player_search,
+    changed_transform_queries
+);
+criterion_main!(benches);
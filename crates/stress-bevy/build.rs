@@ -0,0 +1,76 @@
+//! Bakes build details into the binaries, for the environment header of metrics files.
+
+use std::{env, process::Command};
+
+// What a build depends on, and what the dirty flag looks at
+const PACKAGE_INPUTS: &[&str] = &[
+    "build.rs",
+    "Cargo.toml",
+    "src",
+    "benches",
+    "tests",
+    "assets",
+];
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = output(Command::new(rustc).arg("--version")).unwrap_or_default();
+    println!("cargo:rustc-env=STRESS_RUSTC_VERSION={}", rustc_version);
+
+    println!(
+        "cargo:rustc-env=STRESS_PROFILE={}",
+        env::var("PROFILE").unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=STRESS_OPT_LEVEL={}",
+        env::var("OPT_LEVEL").unwrap_or_default()
+    );
+
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(name, _)| {
+            let feature = name.strip_prefix("CARGO_FEATURE_")?;
+            Some(feature.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=STRESS_FEATURES={}", features.join(","));
+
+    // Marked dirty when this package has uncommitted changes, since the hash alone would be
+    // misleading
+    let commit = output(Command::new("git").args(["rev-parse", "HEAD"])).map(|commit| {
+        let dirty = output(Command::new("git").args(["status", "--porcelain", "--", "."]))
+            .map_or(false, |status| !status.is_empty());
+        if dirty {
+            format!("{}-dirty", commit)
+        } else {
+            commit
+        }
+    });
+    println!(
+        "cargo:rustc-env=STRESS_GIT_COMMIT={}",
+        commit.unwrap_or_default()
+    );
+
+    // Rebuild on commit, checkout and staging too
+    if let Some(git_dir) = output(Command::new("git").args(["rev-parse", "--git-dir"])) {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        println!("cargo:rerun-if-changed={}/index", git_dir);
+        if let Some(head) = output(Command::new("git").args(["symbolic-ref", "HEAD"])) {
+            println!("cargo:rerun-if-changed={}/{}", git_dir, head);
+        }
+    }
+
+    // Listing any file turns off cargo's rerun on every package change, so the package's own
+    // inputs go back in for the dirty flag to notice edits
+    for input in PACKAGE_INPUTS {
+        println!("cargo:rerun-if-changed={}", input);
+    }
+}
+
+fn output(command: &mut Command) -> Option<String> {
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
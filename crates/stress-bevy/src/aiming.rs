@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    camera::GameCamera,
+    character::Character,
+    collision_layers::CollisionMatrix,
+    combat::Projectile,
+};
+
+const AIM_RANGE: f32 = 1000.;
+
+/// Where the crosshair points: a ray from the camera through the centre of the screen, and the
+/// first thing it hits (or a point far along it).
+#[derive(Debug, Default)]
+pub struct Aim {
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub point: Vec3,
+}
+
+impl Aim {
+    /// Direction from `from` to the aimed point, falling back to the camera's direction when
+    /// `from` is on top of it.
+    pub fn direction_from(&self, from: Vec3) -> Vec3 {
+        let direction = (self.point - from).normalize_or_zero();
+        if direction == Vec3::ZERO {
+            self.direction
+        } else {
+            direction
+        }
+    }
+}
+
+pub fn update_aim(
+    mut aim: ResMut<Aim>,
+    camera: Query<&Transform, With<GameCamera>>,
+    character: Query<Entity, With<Character>>,
+    projectiles: Query<Entity, With<Projectile>>,
+    rapier_context: Res<RapierContext>,
+    layers: Res<CollisionMatrix>,
+) {
+    let camera = match camera.get_single() {
+        Ok(x) => x,
+        _ => return,
+    };
+
+    // The camera sits behind the character, so don't let the ray stop on it or on our own shots
+    let character = character.get_single().ok();
+    let filter =
+        |entity: Entity| Some(entity) != character && projectiles.get(entity).is_err();
+
+    let origin = camera.translation;
+    let direction = camera.forward();
+    let toi = rapier_context
+        .cast_ray(
+            origin,
+            direction,
+            AIM_RANGE,
+            true,
+            layers.shot_query_groups(),
+            Some(&filter),
+        )
+        .map_or(AIM_RANGE, |(_, toi)| toi);
+
+    *aim = Aim {
+        origin,
+        direction,
+        point: origin + direction * toi,
+    };
+}
@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+use crate::{
+    archetypes::{MonsterKind, MonsterRegistry},
+    camera::camera_input_map,
+    character::{Character, CharacterAnimations},
+    scenario::Scenario,
+};
+
+#[derive(Component)]
+pub struct HackyHeightFix;
+
+#[derive(Debug, Component)]
+pub struct AnimationHelperSetup;
+#[derive(Debug, Component)]
+pub struct AnimationHelper(pub Entity);
+
+/// Seats glTF scenes on their colliders and starts their idle animations. Primitives mode has
+/// no scenes, and animation can be left off alone.
+pub struct AnimationHelpersPlugin;
+
+impl Plugin for AnimationHelpersPlugin {
+    fn build(&self, app: &mut App) {
+        let scenario = app.world.resource::<Scenario>();
+        let uses_gltf = scenario.uses_gltf();
+        let animation = uses_gltf && scenario.animation;
+
+        if animation {
+            app.add_system(setup_scene_once_loaded)
+                .add_system(setup_helpers);
+        }
+        if uses_gltf {
+            app.add_system(hacky_height_fix.after(camera_input_map));
+        }
+    }
+}
+
+// Once the scene is loaded, start the animation
+fn setup_scene_once_loaded(
+    registry: Option<Res<MonsterRegistry>>,
+    character_animations: Res<CharacterAnimations>,
+    monsters: Query<&MonsterKind>,
+    characters: Query<&Character>,
+    animation_helpers: Query<(Entity, &AnimationHelper)>,
+    mut players: Query<&mut AnimationPlayer>,
+    mut commands: Commands,
+) {
+    for (id, &AnimationHelper(player_id)) in animation_helpers.iter() {
+        if let (Ok(&kind), Some(registry)) = (monsters.get(id), &registry) {
+            if let Some(idle) = &registry.kind(kind).idle {
+                let mut player = players.get_mut(player_id).unwrap();
+                player.play(idle.clone_weak()).repeat();
+            }
+        } else if characters.get(id).is_ok() {
+            let mut player = players.get_mut(player_id).unwrap();
+            player.play(character_animations.idle.clone_weak()).repeat();
+        }
+
+        commands.entity(id).remove::<AnimationHelper>();
+    }
+}
+
+pub fn hacky_height_fix(
+    children: Query<&Children>,
+    mut transforms: Query<&mut Transform>,
+    characters: Query<Entity, (With<Character>, With<HackyHeightFix>)>,
+    monsters: Query<(Entity, &MonsterKind), With<HackyHeightFix>>,
+    registry: Option<Res<MonsterRegistry>>,
+    mut commands: Commands,
+) {
+    for character in characters.iter() {
+        let children = children.get(character).unwrap();
+        for child in children.iter() {
+            transforms.get_mut(*child).unwrap().translation.y = -9.;
+            commands.entity(character).remove::<HackyHeightFix>();
+        }
+    }
+
+    let registry = match registry {
+        Some(x) => x,
+        _ => return,
+    };
+
+    for (monster, &kind) in monsters.iter() {
+        let offset = registry.kind(kind).archetype.model_offset;
+        let children = children.get(monster).unwrap();
+        for child in children.iter() {
+            transforms.get_mut(*child).unwrap().translation.y = offset;
+            commands.entity(monster).remove::<HackyHeightFix>();
+        }
+    }
+}
+
+pub fn setup_helpers(
+    mut commands: Commands,
+    to_setup: Query<Entity, With<AnimationHelperSetup>>,
+    children: Query<&Children>,
+    players: Query<&AnimationPlayer>,
+) {
+    for host_entity in to_setup.iter() {
+        if let Some(animation_player) =
+            find_animation_player_entity(host_entity, &children, &players)
+        {
+            commands
+                .entity(host_entity)
+                .remove::<AnimationHelperSetup>()
+                .insert(AnimationHelper(animation_player)); // This is how I find it later and  what I query for
+        }
+    }
+}
+
+pub fn find_animation_player_entity(
+    parent: Entity,
+    children: &Query<&Children>,
+    players: &Query<&AnimationPlayer>,
+) -> Option<Entity> {
+    if let Ok(candidates) = children.get(parent) {
+        let mut next_candidates: Vec<Entity> = candidates.iter().map(|e| e.to_owned()).collect();
+        while !next_candidates.is_empty() {
+            for candidate in next_candidates.drain(..).collect::<Vec<Entity>>() {
+                if players.get(candidate).is_ok() {
+                    return Some(candidate);
+                } else if let Ok(new) = children.get(candidate) {
+                    next_candidates.extend(new.iter());
+                }
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,275 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadState, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use bevy_rapier3d::prelude::*;
+use bevy_turborand::rng::{CellState, Rng};
+use serde::Deserialize;
+
+use crate::{assets::AssetManifest, scenario::Scenario};
+
+pub const MONSTER_ARCHETYPES: &str = "monsters.archetypes.ron";
+
+/// A kind of monster, as described in `assets/monsters.archetypes.ron`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MonsterArchetype {
+    pub name: String,
+    /// glTF scene drawn for this monster.
+    pub model: String,
+    /// How far the scene is moved down to line up with the collider.
+    pub model_offset: f32,
+    pub animations: MonsterAnimationSet,
+    pub collider: ColliderShape,
+    pub mass: f32,
+    pub gravity_scale: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    pub health: f32,
+    /// How fast the monster walks toward the character.
+    pub speed: f32,
+    /// Relative chance of this archetype being picked for a wave slot.
+    pub spawn_weight: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MonsterAnimationSet {
+    pub idle: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum ColliderShape {
+    Cuboid { half_extents: Vec3 },
+    Capsule { half_height: f32, radius: f32 },
+    Ball { radius: f32 },
+}
+
+impl ColliderShape {
+    pub fn collider(&self) -> Collider {
+        match *self {
+            ColliderShape::Cuboid { half_extents } => {
+                Collider::cuboid(half_extents.x, half_extents.y, half_extents.z)
+            }
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+            } => Collider::capsule_y(half_height, radius),
+            ColliderShape::Ball { radius } => Collider::ball(radius),
+        }
+    }
+
+    pub fn volume(&self) -> f32 {
+        match *self {
+            ColliderShape::Cuboid { half_extents } => {
+                8. * half_extents.x * half_extents.y * half_extents.z
+            }
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+            } => PI * radius * radius * (2. * half_height + 4. / 3. * radius),
+            ColliderShape::Ball { radius } => 4. / 3. * PI * radius * radius * radius,
+        }
+    }
+
+    /// Half extents of the shape's bounding box.
+    pub fn half_extents(&self) -> Vec3 {
+        match *self {
+            ColliderShape::Cuboid { half_extents } => half_extents,
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+            } => Vec3::new(radius, half_height + radius, radius),
+            ColliderShape::Ball { radius } => Vec3::splat(radius),
+        }
+    }
+}
+
+// The monster `spawn_monster` used before archetypes were data driven, used if the archetype file
+// can't be loaded.
+impl Default for MonsterArchetype {
+    fn default() -> Self {
+        Self {
+            name: "grunt".to_string(),
+            model: "monster-idleGLTF.glb#Scene0".to_string(),
+            model_offset: -3.,
+            animations: MonsterAnimationSet {
+                idle: Some("monster-idleGLTF.glb#Animation0".to_string()),
+            },
+            collider: ColliderShape::Cuboid {
+                half_extents: Vec3::new(1., 3., 1.),
+            },
+            mass: 24.,
+            gravity_scale: 10.,
+            linear_damping: 0.5,
+            angular_damping: 1.0,
+            health: 100.,
+            speed: 5.,
+            spawn_weight: 1,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "0f3b8e4c-5a1d-4c7e-9b62-3d8f1a7c2e90"]
+pub struct MonsterArchetypes {
+    pub archetypes: Vec<MonsterArchetype>,
+}
+
+#[derive(Default)]
+struct MonsterArchetypesLoader;
+
+impl AssetLoader for MonsterArchetypesLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let archetypes: MonsterArchetypes = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(archetypes));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["archetypes.ron"]
+    }
+}
+
+/// Index of a monster's archetype in the [`MonsterRegistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
+pub struct MonsterKind(pub usize);
+
+#[derive(Debug, Component)]
+pub struct Health(pub f32);
+
+#[derive(Debug, Component)]
+pub struct MoveSpeed(pub f32);
+
+pub struct RegisteredArchetype {
+    pub archetype: MonsterArchetype,
+    pub scene: Handle<Scene>,
+    pub idle: Option<Handle<AnimationClip>>,
+}
+
+/// Every monster archetype, looked up by [`MonsterKind`].
+pub struct MonsterRegistry {
+    archetypes: Vec<RegisteredArchetype>,
+}
+
+impl MonsterRegistry {
+    pub fn new(archetypes: Vec<RegisteredArchetype>) -> Self {
+        Self { archetypes }
+    }
+
+    pub fn kind(&self, MonsterKind(index): MonsterKind) -> &RegisteredArchetype {
+        &self.archetypes[index]
+    }
+
+    /// Picks an archetype at random, weighted by `spawn_weight`.
+    pub fn choose(&self, rng: &Rng<CellState>) -> MonsterKind {
+        let total: u32 = self
+            .archetypes
+            .iter()
+            .map(|registered| registered.archetype.spawn_weight)
+            .sum();
+        if total == 0 {
+            return MonsterKind(0);
+        }
+
+        let mut roll = rng.u32(0..total);
+        for (index, registered) in self.archetypes.iter().enumerate() {
+            if roll < registered.archetype.spawn_weight {
+                return MonsterKind(index);
+            }
+            roll -= registered.archetype.spawn_weight;
+        }
+        MonsterKind(self.archetypes.len() - 1)
+    }
+}
+
+struct MonsterArchetypesHandle(Handle<MonsterArchetypes>);
+
+pub struct MonsterArchetypePlugin;
+
+impl Plugin for MonsterArchetypePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<MonsterArchetypes>()
+            .init_asset_loader::<MonsterArchetypesLoader>()
+            .add_startup_system(load_monster_archetypes)
+            .add_system(build_monster_registry);
+    }
+}
+
+fn load_monster_archetypes(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(MonsterArchetypesHandle(
+        asset_server.load(MONSTER_ARCHETYPES),
+    ));
+}
+
+fn build_monster_registry(
+    mut commands: Commands,
+    handle: Res<MonsterArchetypesHandle>,
+    registry: Option<Res<MonsterRegistry>>,
+    definitions: Res<Assets<MonsterArchetypes>>,
+    scenario: Res<Scenario>,
+    asset_server: Res<AssetServer>,
+    mut manifest: ResMut<AssetManifest>,
+) {
+    if registry.is_some() {
+        return;
+    }
+
+    let mut archetypes = match asset_server.get_load_state(&handle.0) {
+        LoadState::Loaded => definitions.get(&handle.0).unwrap().archetypes.clone(),
+        LoadState::Failed => Vec::new(),
+        _ => return,
+    };
+
+    if archetypes.is_empty() {
+        warn!("no monster archetypes loaded, using the built-in one");
+        archetypes.push(MonsterArchetype::default());
+    }
+
+    let archetypes = archetypes
+        .into_iter()
+        .map(|archetype| {
+            let (scene, idle) = if scenario.uses_gltf() {
+                manifest.add(
+                    &asset_server,
+                    format!("{} scene", archetype.name),
+                    &archetype.model,
+                );
+                if let Some(idle) = &archetype.animations.idle {
+                    manifest.add(
+                        &asset_server,
+                        format!("{} idle animation", archetype.name),
+                        idle,
+                    );
+                }
+
+                (
+                    asset_server.load(archetype.model.as_str()),
+                    archetype
+                        .animations
+                        .idle
+                        .as_ref()
+                        .map(|idle| asset_server.load(idle.as_str())),
+                )
+            } else {
+                (Handle::default(), None)
+            };
+
+            RegisteredArchetype {
+                archetype,
+                scene,
+                idle,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    info!("registered {} monster archetypes", archetypes.len());
+    commands.insert_resource(MonsterRegistry::new(archetypes));
+}
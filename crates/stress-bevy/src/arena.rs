@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    collision_layers::{CollisionMatrix, Layer},
+    scenario::Scenario,
+};
+
+#[derive(Component)]
+pub struct Ground;
+
+/// The ground everything stands on, sized by the scenario.
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_arena);
+    }
+}
+
+fn spawn_arena(mut commands: Commands, scenario: Res<Scenario>, layers: Res<CollisionMatrix>) {
+    let arena_half = scenario.arena_size / 2.;
+    commands
+        .spawn()
+        .insert(Ground)
+        .insert(Collider::cuboid(arena_half, 0.01, arena_half))
+        .insert_bundle(layers.bundle(Layer::Ground))
+        .insert(RigidBody::KinematicPositionBased)
+        .insert_bundle(TransformBundle::from(Transform::from_xyz(0.0, -2.0, 0.0)));
+}
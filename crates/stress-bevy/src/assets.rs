@@ -0,0 +1,140 @@
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::{archetypes::MONSTER_ARCHETYPES, scenario::Scenario};
+
+pub const CROSSHAIR_IMAGE: &str = "crosshair.png";
+pub const OVERLAY_FONT: &str = "fonts/DejaVuSansMono.ttf";
+pub const CHARACTER_SCENE: &str = "m_player.glb#Scene0";
+pub const CHARACTER_IDLE: &str = "m_player.glb#Animation0";
+
+// Every asset the stress test depends on, as (label, path). Monster models are added by
+// `build_monster_registry` once the archetypes are known.
+const MANIFEST: &[(&str, &str)] = &[("monster archetypes", MONSTER_ARCHETYPES)];
+
+// Only loaded when there's a screen to draw them on.
+const UI_MANIFEST: &[(&str, &str)] = &[
+    ("crosshair", CROSSHAIR_IMAGE),
+    ("overlay font", OVERLAY_FONT),
+];
+
+// Only loaded when the character is drawn from a glTF scene.
+const MODEL_MANIFEST: &[(&str, &str)] = &[
+    ("character scene", CHARACTER_SCENE),
+    ("character idle animation", CHARACTER_IDLE),
+];
+
+pub struct AssetManifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+pub struct ManifestEntry {
+    pub label: String,
+    pub path: String,
+    pub handle: HandleUntyped,
+    pub state: LoadState,
+}
+
+impl ManifestEntry {
+    /// True once the entry has either loaded or failed.
+    pub fn is_settled(&self) -> bool {
+        matches!(self.state, LoadState::Loaded | LoadState::Failed)
+    }
+}
+
+impl AssetManifest {
+    /// Starts loading `path`, unless it's already in the manifest: archetypes can share a model.
+    pub fn add(&mut self, asset_server: &AssetServer, label: String, path: &str) {
+        if self.entries.iter().any(|entry| entry.path == path) {
+            return;
+        }
+        self.entries.push(ManifestEntry {
+            label,
+            path: path.to_string(),
+            handle: asset_server.load_untyped(path),
+            state: LoadState::NotLoaded,
+        });
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.entries.iter().all(ManifestEntry::is_settled)
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &ManifestEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.state == LoadState::Failed)
+    }
+}
+
+pub struct AssetManifestPlugin;
+
+impl Plugin for AssetManifestPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PreStartup, load_asset_manifest)
+            .add_system(validate_asset_manifest);
+    }
+}
+
+fn load_asset_manifest(
+    mut commands: Commands,
+    scenario: Res<Scenario>,
+    asset_server: Res<AssetServer>,
+) {
+    let ui: &[_] = if cfg!(feature = "render") {
+        UI_MANIFEST
+    } else {
+        &[]
+    };
+    let models: &[_] = if scenario.uses_gltf() {
+        MODEL_MANIFEST
+    } else {
+        &[]
+    };
+    let mut manifest = AssetManifest {
+        entries: Vec::new(),
+    };
+    for &(label, path) in MANIFEST.iter().chain(ui).chain(models) {
+        manifest.add(&asset_server, label.to_string(), path);
+    }
+
+    commands.insert_resource(manifest);
+}
+
+pub fn validate_asset_manifest(
+    mut manifest: ResMut<AssetManifest>,
+    asset_server: Res<AssetServer>,
+) {
+    if manifest.is_settled() {
+        return;
+    }
+
+    for entry in manifest.entries.iter_mut() {
+        let state = asset_server.get_load_state(&entry.handle);
+        if state == entry.state {
+            continue;
+        }
+
+        match state {
+            LoadState::Failed => error!(
+                "missing or failed asset: {} ({}), using a placeholder",
+                entry.label, entry.path
+            ),
+            LoadState::Loaded => debug!("loaded asset: {} ({})", entry.label, entry.path),
+            _ => {}
+        }
+        entry.state = state;
+    }
+
+    if manifest.is_settled() {
+        let failed = manifest.failed().count();
+        if failed == 0 {
+            info!("all {} manifest assets loaded", manifest.entries.len());
+        } else {
+            warn!(
+                "{} of {} manifest assets failed to load",
+                failed,
+                manifest.entries.len()
+            );
+        }
+    }
+}
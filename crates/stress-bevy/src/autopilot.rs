@@ -0,0 +1,141 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::{
+    input::{mouse::MouseMotion, InputSystem},
+    prelude::*,
+};
+
+use crate::{
+    camera::{look_at_character, place_camera, GameCamera, LookTransform},
+    character::{move_character, Character},
+    combat::launch_projectile,
+    loading::AppState,
+    scenario::{Autopilot, Scenario, StrafePattern},
+    simulation::{advance_clock, SimulationClock},
+    waves::Monster,
+};
+
+/// Seconds between direction changes for the sweeping patterns.
+const STRAFE_PERIOD: f64 = 2.;
+
+/// The keyboard and mouse state the bot is holding, copied over the real input every frame.
+#[derive(Default)]
+struct BotInput {
+    keys: Input<KeyCode>,
+    buttons: Input<MouseButton>,
+    last_trigger: Option<f64>,
+}
+
+pub struct AutopilotPlugin;
+
+impl Plugin for AutopilotPlugin {
+    fn build(&self, app: &mut App) {
+        let autopilot = match app.world.resource::<Scenario>().autopilot {
+            Some(autopilot) => autopilot,
+            None => return,
+        };
+        info!("autopilot: {:?}", autopilot);
+
+        app.insert_resource(autopilot)
+            .init_resource::<BotInput>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                drive_inputs.after(InputSystem).after(advance_clock),
+            )
+            .add_system(
+                turn_toward_nearest_monster
+                    .after(look_at_character)
+                    .before(move_character)
+                    .before(place_camera)
+                    .before(launch_projectile),
+            );
+    }
+}
+
+pub fn drive_inputs(
+    mut bot: ResMut<BotInput>,
+    autopilot: Res<Autopilot>,
+    state: Res<State<AppState>>,
+    clock: Res<SimulationClock>,
+    mut keyboard: ResMut<Input<KeyCode>>,
+    mut mouse_button: ResMut<Input<MouseButton>>,
+    mut mouse_motion: ResMut<Events<MouseMotion>>,
+) {
+    // The bot has the controls to itself
+    mouse_motion.clear();
+    bot.keys.clear();
+    bot.buttons.clear();
+
+    if *state.current() == AppState::Playing {
+        let phase = (clock.elapsed / STRAFE_PERIOD) as u64 % 2 == 0;
+        let held: &[KeyCode] = match autopilot.pattern {
+            StrafePattern::Circle => &[KeyCode::D],
+            StrafePattern::Sweep if phase => &[KeyCode::A],
+            StrafePattern::Sweep => &[KeyCode::D],
+            StrafePattern::Zigzag if phase => &[KeyCode::W, KeyCode::A],
+            StrafePattern::Zigzag => &[KeyCode::W, KeyCode::D],
+        };
+        for key in [KeyCode::W, KeyCode::A, KeyCode::S, KeyCode::D] {
+            if held.contains(&key) {
+                bot.keys.press(key);
+            } else {
+                bot.keys.release(key);
+            }
+        }
+
+        // Pulls are one tick long so semi-automatic weapons see every one of them
+        bot.buttons.release(MouseButton::Left);
+        let due = bot.last_trigger.map_or(true, |last| {
+            clock.elapsed - last >= 1. / autopilot.fire_rate as f64
+        });
+        if due {
+            bot.buttons.press(MouseButton::Left);
+            bot.last_trigger = Some(clock.elapsed);
+        }
+    }
+
+    *keyboard = bot.keys.clone();
+    *mouse_button = bot.buttons.clone();
+}
+
+// Orbits the camera so the crosshair sweeps toward the closest monster
+fn turn_toward_nearest_monster(
+    autopilot: Res<Autopilot>,
+    clock: Res<SimulationClock>,
+    mut cameras: Query<&mut LookTransform, With<GameCamera>>,
+    character: Query<&Transform, With<Character>>,
+    monsters: Query<&Transform, With<Monster>>,
+) {
+    let character = match character.get_single() {
+        Ok(x) => x.translation,
+        _ => return,
+    };
+    let nearest = monsters
+        .iter()
+        .map(|monster| monster.translation)
+        .min_by(|a, b| {
+            a.distance_squared(character)
+                .total_cmp(&b.distance_squared(character))
+        });
+    let nearest = match nearest {
+        Some(x) => x,
+        None => return,
+    };
+
+    for mut look in cameras.iter_mut() {
+        let offset = look.eye - look.target;
+        let toward = nearest - look.target;
+        if toward.x == 0. && toward.z == 0. {
+            continue;
+        }
+
+        // The eye sits opposite the monster, so the camera looks through the target at it
+        let current = offset.x.atan2(offset.z);
+        let wanted = (-toward.x).atan2(-toward.z);
+        let turn = (wanted - current + PI).rem_euclid(TAU) - PI;
+        let max_turn = autopilot.turn_speed * clock.dt;
+
+        let rotation = Quat::from_rotation_y(turn.clamp(-max_turn, max_turn));
+        look.eye = look.target + rotation * offset;
+    }
+}
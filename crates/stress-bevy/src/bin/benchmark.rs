@@ -0,0 +1,34 @@
+//! The game without a window or GPU, for measuring. Takes the same arguments as the game, and
+//! needs `--ticks` since there's no window to close. Built with `--no-default-features`, it
+//! doesn't link the graphics stack at all.
+
+use std::process;
+
+use bevy::{log::LogPlugin, prelude::*};
+use stress_bevy::{headless::HeadlessPlugins, profiling, scenario::Scenario, GamePlugins};
+
+fn main() {
+    let scenario = Scenario::from_args();
+    if scenario.ticks.is_none() {
+        eprintln!("--ticks is required, since nothing else ends a headless run");
+        process::exit(2);
+    }
+    let profiling = profiling::init(&scenario);
+
+    let mut app = App::new();
+    app.insert_resource(scenario.collision.clone())
+        .insert_resource(scenario);
+
+    // Profiling installs its own subscriber, which does the logging too
+    match profiling {
+        Some(profiling) => {
+            app.add_plugins_with(HeadlessPlugins, |group| group.disable::<LogPlugin>());
+            profiling.add_to(&mut app);
+        }
+        None => {
+            app.add_plugins(HeadlessPlugins);
+        }
+    }
+
+    app.add_plugins(GamePlugins).run();
+}
@@ -0,0 +1,269 @@
+//! Compares the frame times of two metrics files, wave by wave or by monster count.
+//!
+//! Usage: `compare_metrics <baseline.json> <candidate.json> [options]`
+//!
+//! - `--by=wave` (default) or `--by=monsters`, grouping frames by the wave they were in or by
+//!   monster count in buckets of `--bucket=N` (default 100)
+//! - `--threshold=PERCENT` (default 5): exit with 1 when a significant median or p99 slowdown
+//!   exceeds this
+//! - `--resamples=N` (default 1000) and `--confidence=X` (default 0.95) for the bootstrap
+//! - `--min-frames=N` (default 30): skip groups with fewer frames in either run
+//! - `--include-transient`: keep warmup and post-wave frames, which are left out by default
+//! - `--strict`: refuse to compare runs from different machines, builds or scenarios, instead
+//!   of warning
+
+use std::{collections::BTreeMap, process, str::FromStr};
+
+use stress_bevy::{
+    metrics::{FrameRecord, MetricsFile},
+    stats::{self, Bootstrap, Delta},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GroupBy {
+    Wave,
+    Monsters { bucket: u32 },
+}
+
+impl GroupBy {
+    fn key(&self, frame: &FrameRecord) -> u32 {
+        match *self {
+            GroupBy::Wave => frame.wave,
+            GroupBy::Monsters { bucket } => frame.monsters / bucket * bucket,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            GroupBy::Wave => "wave",
+            GroupBy::Monsters { .. } => "monsters",
+        }
+    }
+}
+
+struct Options {
+    baseline: String,
+    candidate: String,
+    by: GroupBy,
+    threshold: f64,
+    min_frames: usize,
+    strict: bool,
+    include_transient: bool,
+    bootstrap: Bootstrap,
+}
+
+fn main() {
+    let options = parse_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(2);
+    });
+
+    let [baseline, candidate] = [&options.baseline, &options.candidate].map(|path| {
+        MetricsFile::read(path).unwrap_or_else(|err| {
+            eprintln!("failed to read {}: {}", path, err);
+            process::exit(2);
+        })
+    });
+    println!(
+        "baseline:  {} ({} frames)",
+        options.baseline,
+        baseline.frames.len()
+    );
+    println!(
+        "candidate: {} ({} frames)",
+        options.candidate,
+        candidate.frames.len()
+    );
+    println!();
+
+    if !environments_match(&baseline, &candidate) {
+        if options.strict {
+            eprintln!("refusing to compare runs from different environments");
+            process::exit(2);
+        }
+        println!("timings may not be comparable\n");
+    }
+
+    let frames_a: Vec<&FrameRecord> = baseline.summary_frames(options.include_transient).collect();
+    let frames_b: Vec<&FrameRecord> = candidate
+        .summary_frames(options.include_transient)
+        .collect();
+    if frames_a.is_empty() || frames_b.is_empty() {
+        eprintln!("nothing to compare, a run recorded no frames");
+        process::exit(2);
+    }
+    let all_a = stats::sorted(frames_a.iter().map(|frame| frame.frame_time_ms));
+    let all_b = stats::sorted(frames_b.iter().map(|frame| frame.frame_time_ms));
+    let groups_a = group(&frames_a, options.by);
+    let groups_b = group(&frames_b, options.by);
+    let mut rows: Vec<(String, &[f64], &[f64])> = groups_a
+        .iter()
+        .filter_map(|(key, a)| {
+            let b = groups_b.get(key)?;
+            Some((key.to_string(), a.as_slice(), b.as_slice()))
+        })
+        .filter(|(_, a, b)| a.len() >= options.min_frames && b.len() >= options.min_frames)
+        .collect();
+
+    rows.push(("all".to_string(), &all_a, &all_b));
+
+    println!(
+        "{:>8} {:>13}  {:<46}  {:<46}",
+        options.by.label(),
+        "frames",
+        "median ms",
+        "p99 ms"
+    );
+
+    let mut regressions = 0;
+    for (key, a, b) in rows {
+        if a.is_empty() || b.is_empty() {
+            continue;
+        }
+
+        let median = options.bootstrap.delta(a, b, stats::median);
+        let p99 = options
+            .bootstrap
+            .delta(a, b, |samples| stats::percentile(samples, 99.));
+        println!(
+            "{:>8} {:>6}/{:<6}  {}  {}",
+            key,
+            a.len(),
+            b.len(),
+            format_delta(&median, options.threshold),
+            format_delta(&p99, options.threshold),
+        );
+
+        regressions += [median, p99]
+            .iter()
+            .filter(|delta| is_regression(delta, options.threshold))
+            .count();
+    }
+
+    println!();
+    if regressions > 0 {
+        println!(
+            "{} significant slowdowns over {}%",
+            regressions, options.threshold
+        );
+        process::exit(1);
+    }
+    println!("no significant slowdowns over {}%", options.threshold);
+}
+
+// Warns about every difference, returning false if there were any
+fn environments_match(baseline: &MetricsFile, candidate: &MetricsFile) -> bool {
+    let (a, b) = match (&baseline.environment, &candidate.environment) {
+        (Some(a), Some(b)) => (a, b),
+        _ => {
+            println!("warning: a run has no environment recorded");
+            return false;
+        }
+    };
+
+    let differences = a.differences(b);
+    for (field, a, b) in &differences {
+        println!(
+            "warning: {} differs\n  baseline:  {}\n  candidate: {}",
+            field, a, b
+        );
+    }
+    differences.is_empty()
+}
+
+fn group(frames: &[&FrameRecord], by: GroupBy) -> BTreeMap<u32, Vec<f64>> {
+    let mut groups: BTreeMap<u32, Vec<f64>> = BTreeMap::new();
+    for frame in frames {
+        groups
+            .entry(by.key(frame))
+            .or_default()
+            .push(frame.frame_time_ms);
+    }
+    for samples in groups.values_mut() {
+        samples.sort_by(f64::total_cmp);
+    }
+    groups
+}
+
+fn is_regression(delta: &Delta, threshold: f64) -> bool {
+    delta.is_significant() && delta.percent > threshold
+}
+
+fn format_delta(delta: &Delta, threshold: f64) -> String {
+    let label = if is_regression(delta, threshold) {
+        "REGRESSION"
+    } else if delta.is_significant() {
+        "significant"
+    } else {
+        "noise"
+    };
+
+    format!(
+        "{:>7.2} -> {:>7.2} {:>+7.1}% [{:>+6.1}, {:>+6.1}] {:<11}",
+        delta.baseline, delta.candidate, delta.percent, delta.low, delta.high, label
+    )
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut paths = Vec::new();
+    let mut by = "wave".to_string();
+    let mut bucket = 100;
+    let mut options = Options {
+        baseline: String::new(),
+        candidate: String::new(),
+        by: GroupBy::Wave,
+        threshold: 5.,
+        min_frames: 30,
+        strict: false,
+        include_transient: false,
+        bootstrap: Bootstrap::default(),
+    };
+
+    for arg in args {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (arg.as_str(), None),
+        };
+
+        match name {
+            "--by" => by = parse_value(name, value)?,
+            "--bucket" => bucket = parse_value(name, value)?,
+            "--threshold" => options.threshold = parse_value(name, value)?,
+            "--min-frames" => options.min_frames = parse_value(name, value)?,
+            "--resamples" => options.bootstrap.resamples = parse_value(name, value)?,
+            "--confidence" => options.bootstrap.confidence = parse_value(name, value)?,
+            "--strict" => options.strict = true,
+            "--include-transient" => options.include_transient = true,
+            _ if name.starts_with("--") => return Err(format!("unknown argument '{}'", arg)),
+            _ => paths.push(arg),
+        }
+    }
+
+    options.by = match by.as_str() {
+        "wave" => GroupBy::Wave,
+        "monsters" if bucket > 0 => GroupBy::Monsters { bucket },
+        "monsters" => return Err("--bucket must be at least 1".to_string()),
+        _ => return Err(format!("can't group by '{}', use wave or monsters", by)),
+    };
+    let confidence = options.bootstrap.confidence;
+    if confidence.is_nan() || confidence <= 0. || confidence >= 1. {
+        return Err("--confidence must be between 0 and 1, like 0.95".to_string());
+    }
+
+    match <[String; 2]>::try_from(paths) {
+        Ok([baseline, candidate]) => {
+            options.baseline = baseline;
+            options.candidate = candidate;
+            Ok(options)
+        }
+        Err(_) => {
+            Err("usage: compare_metrics <baseline.json> <candidate.json> [options]".to_string())
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(name: &str, value: Option<&str>) -> Result<T, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("{} expects a value, like {}=<value>", name, name))
+}
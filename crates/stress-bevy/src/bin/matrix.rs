@@ -0,0 +1,275 @@
+//! Runs every combination of a set of scenario parameters as headless apps in this process, and
+//! writes all of the metrics to one results file.
+//!
+//! Usage: `matrix [options] [scenario args...]`
+//!
+//! - `--monsters-per-wave=N,...` (default 10)
+//! - `--arena-size=N,...` (default 500)
+//! - `--pooling=on,off`, `--ccd=on,off` and `--animation=on,off` (default off, off and on)
+//! - `--repetitions=N` (default 3): runs per combination, each in a fresh app
+//! - `--ticks=N` (default 1200): simulation ticks per run
+//! - `--out=PATH` (default matrix.json)
+//!
+//! Anything else is passed on to every run. The autopilot is turned on unless a replay is given,
+//! since projectiles only get fired by someone playing. System profiling isn't available, since
+//! its subscriber is global to the process.
+
+use std::{collections::BTreeMap, env, path::PathBuf, process, str::FromStr};
+
+use stress_bevy::{
+    headless::run_headless,
+    matrix::{MatrixResults, MatrixRun},
+    metrics::FrameRecord,
+    scenario::Scenario,
+    stats,
+};
+
+/// A parameter and the values to sweep it over.
+struct Axis {
+    name: &'static str,
+    values: Vec<String>,
+}
+
+impl Axis {
+    /// The scenario argument for one of its values.
+    fn arg(&self, value: &str) -> Option<String> {
+        match (self.name, value) {
+            ("pooling", "on") => Some("--pooling".to_string()),
+            ("ccd", "on") => Some("--ccd".to_string()),
+            ("animation", "off") => Some("--no-animation".to_string()),
+            ("pooling" | "ccd" | "animation", _) => None,
+            (name, value) => Some(format!("--{}={}", name, value)),
+        }
+    }
+}
+
+struct Options {
+    axes: Vec<Axis>,
+    repetitions: u32,
+    ticks: u64,
+    out: PathBuf,
+    passthrough: Vec<String>,
+}
+
+fn main() {
+    let options = parse_args(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(2);
+    });
+
+    let combinations = combinations(&options.axes);
+    let total = combinations.len() * options.repetitions as usize;
+    let mut results = MatrixResults::default();
+
+    for parameters in &combinations {
+        let mut args = options.passthrough.clone();
+        args.push(format!("--ticks={}", options.ticks));
+        args.extend(
+            options
+                .axes
+                .iter()
+                .filter_map(|axis| axis.arg(&parameters[axis.name])),
+        );
+        let scenario = Scenario::parse(args).unwrap_or_else(|err| {
+            eprintln!("{}: {}", describe(parameters), err);
+            process::exit(2);
+        });
+
+        for repetition in 1..=options.repetitions {
+            let metrics = run_headless(scenario.clone());
+            let (median, p99) = summarize(metrics.summary_frames(false));
+            println!(
+                "[{}/{}] {} #{}: median {:.2} ms, p99 {:.2} ms",
+                results.runs.len() + 1,
+                total,
+                describe(parameters),
+                repetition,
+                median,
+                p99
+            );
+
+            results.runs.push(MatrixRun {
+                parameters: parameters
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.clone()))
+                    .collect(),
+                repetition,
+                metrics,
+            });
+        }
+    }
+
+    if let Err(err) = results.write(&options.out) {
+        eprintln!("failed to write {}: {}", options.out.display(), err);
+        process::exit(2);
+    }
+
+    // Repetitions pooled together, steady-state frames only
+    println!();
+    println!("{:>9} {:>9}  parameters", "median ms", "p99 ms");
+    for parameters in &combinations {
+        let frames = results
+            .runs
+            .iter()
+            .filter(|run| {
+                parameters
+                    .iter()
+                    .all(|(name, value)| run.parameters.get(*name) == Some(value))
+            })
+            .flat_map(|run| run.metrics.summary_frames(false));
+        let (median, p99) = summarize(frames);
+        println!("{:>9.2} {:>9.2}  {}", median, p99, describe(parameters));
+    }
+    println!();
+    println!(
+        "wrote {} runs to {}",
+        results.runs.len(),
+        options.out.display()
+    );
+}
+
+// Every pick of one value per axis, varying the last axis fastest
+fn combinations(axes: &[Axis]) -> Vec<BTreeMap<&'static str, String>> {
+    axes.iter()
+        .fold(vec![BTreeMap::new()], |combinations, axis| {
+            combinations
+                .iter()
+                .flat_map(|combination| {
+                    axis.values.iter().map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.insert(axis.name, value.clone());
+                        combination
+                    })
+                })
+                .collect()
+        })
+}
+
+fn describe(parameters: &BTreeMap<&'static str, String>) -> String {
+    parameters
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn summarize<'a>(frames: impl Iterator<Item = &'a FrameRecord>) -> (f64, f64) {
+    let samples = stats::sorted(frames.map(|frame| frame.frame_time_ms));
+    if samples.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    (stats::median(&samples), stats::percentile(&samples, 99.))
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        axes: vec![
+            Axis {
+                name: "monsters-per-wave",
+                values: vec!["10".to_string()],
+            },
+            Axis {
+                name: "arena-size",
+                values: vec!["500".to_string()],
+            },
+            Axis {
+                name: "pooling",
+                values: vec!["off".to_string()],
+            },
+            Axis {
+                name: "ccd",
+                values: vec!["off".to_string()],
+            },
+            Axis {
+                name: "animation",
+                values: vec!["on".to_string()],
+            },
+        ],
+        repetitions: 3,
+        ticks: 1200,
+        out: PathBuf::from("matrix.json"),
+        passthrough: Vec::new(),
+    };
+
+    for arg in args {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (arg.as_str(), None),
+        };
+
+        match name {
+            "--monsters-per-wave" => set_axis::<u32>(&mut options.axes, name, value)?,
+            "--arena-size" => set_axis::<f32>(&mut options.axes, name, value)?,
+            "--pooling" | "--ccd" | "--animation" => {
+                set_axis::<OnOff>(&mut options.axes, name, value)?
+            }
+            "--repetitions" => options.repetitions = parse_value(name, value)?,
+            "--ticks" => options.ticks = parse_value(name, value)?,
+            "--out" => options.out = parse_value(name, value)?,
+            "--metrics" => return Err("--metrics is set by the matrix runner".to_string()),
+            "--profile-systems" | "--chrome-trace" => {
+                return Err(format!("{} can't be used with in-process runs", name))
+            }
+            _ => options.passthrough.push(arg),
+        }
+    }
+
+    if options.repetitions == 0 {
+        return Err("--repetitions must be at least 1".to_string());
+    }
+
+    let has = |name: &str| {
+        options
+            .passthrough
+            .iter()
+            .any(|arg| arg == name || arg.starts_with(&format!("{}=", name)))
+    };
+    if !has("--autopilot") && !has("--replay") {
+        options.passthrough.push("--autopilot".to_string());
+    }
+
+    Ok(options)
+}
+
+/// Replaces an axis's values with a comma separated list, checked against the type the game
+/// parses them as.
+fn set_axis<T: FromStr>(axes: &mut [Axis], name: &str, value: Option<&str>) -> Result<(), String> {
+    let values: Vec<String> = value
+        .unwrap_or_default()
+        .split(',')
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect();
+    if values.is_empty() || values.iter().any(|value| value.parse::<T>().is_err()) {
+        return Err(format!(
+            "{} expects a list of values, like {}=<value>,<value>",
+            name, name
+        ));
+    }
+
+    let axis = axes
+        .iter_mut()
+        .find(|axis| name.strip_prefix("--") == Some(axis.name))
+        .expect("every axis has an argument");
+    axis.values = values;
+    Ok(())
+}
+
+struct OnOff;
+
+impl FromStr for OnOff {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "on" | "off" => Ok(OnOff),
+            _ => Err(()),
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(name: &str, value: Option<&str>) -> Result<T, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("{} expects a value, like {}=<value>", name, name))
+}
@@ -0,0 +1,582 @@
+//! Turns metrics files into a self-contained HTML page of SVG charts.
+//!
+//! Usage: `report [--out=report.html] [--title=TEXT] [--include-transient] [label=]metrics.json...`
+//!
+//! Each file is one series, labelled with its file name unless given as `label=path`, so
+//! `report bevy=bevy.json fyrox=fyrox.json` overlays the two engines. Frame time statistics
+//! leave out warmup and post-wave transients unless `--include-transient` is given.
+
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::Path, process};
+
+use stress_bevy::{
+    metrics::{Environment, FrameRecord, MetricsFile},
+    stats,
+};
+
+const PALETTE: &[&str] = &[
+    "#4c78a8", "#f58518", "#54a24b", "#e45756", "#72b7b2", "#b279a2", "#eeca3b", "#9d755d",
+];
+const WIDTH: f64 = 760.;
+const HEIGHT: f64 = 360.;
+const MARGIN_LEFT: f64 = 64.;
+const MARGIN_RIGHT: f64 = 160.;
+const MARGIN_TOP: f64 = 36.;
+const MARGIN_BOTTOM: f64 = 48.;
+/// Most buckets along the monster count axis.
+const MONSTER_BUCKETS: u32 = 40;
+/// Most points drawn per line, so long runs don't produce huge pages.
+const MAX_POINTS: usize = 600;
+const TOP_SYSTEMS: usize = 15;
+
+struct Run {
+    label: String,
+    color: &'static str,
+    environment: Option<Environment>,
+    frames: Vec<FrameRecord>,
+    /// The frames timing statistics are computed from.
+    summary: Vec<FrameRecord>,
+}
+
+struct Line {
+    name: String,
+    color: &'static str,
+    dashed: bool,
+    points: Vec<(f64, f64)>,
+}
+
+fn main() {
+    let mut out = "report.html".to_string();
+    let mut title = "Stress test report".to_string();
+    let mut include_transient = false;
+    let mut inputs = Vec::new();
+
+    for arg in std::env::args().skip(1) {
+        if let Some(path) = arg.strip_prefix("--out=") {
+            out = path.to_string();
+        } else if let Some(text) = arg.strip_prefix("--title=") {
+            title = text.to_string();
+        } else if arg == "--include-transient" {
+            include_transient = true;
+        } else if arg.starts_with("--") {
+            eprintln!("unknown argument '{}'", arg);
+            process::exit(2);
+        } else {
+            inputs.push(arg);
+        }
+    }
+
+    let mut runs = Vec::new();
+    for input in &inputs {
+        let (label, path) = match input.split_once('=') {
+            Some((label, path)) => (label.to_string(), path),
+            None => (file_stem(input), input.as_str()),
+        };
+        let metrics = MetricsFile::read(path).unwrap_or_else(|err| {
+            eprintln!("failed to read {}: {}", path, err);
+            process::exit(2);
+        });
+        runs.push(Run {
+            label,
+            color: PALETTE[runs.len() % PALETTE.len()],
+            summary: metrics.summary_frames(include_transient).cloned().collect(),
+            environment: metrics.environment,
+            frames: metrics.frames,
+        });
+    }
+
+    if runs.is_empty() {
+        eprintln!(
+            "usage: report [--out=report.html] [--title=TEXT] [--include-transient] \
+             [label=]metrics.json..."
+        );
+        process::exit(2);
+    }
+
+    let html = render(&title, &runs);
+    if let Err(err) = fs::write(&out, html) {
+        eprintln!("failed to write {}: {}", out, err);
+        process::exit(2);
+    }
+    println!("wrote {}", out);
+}
+
+fn file_stem(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map_or_else(|| path.to_string(), |stem| stem.to_string_lossy().into())
+}
+
+fn render(title: &str, runs: &[Run]) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title><style>\
+         body {{ font-family: sans-serif; max-width: 800px; margin: 2em auto; color: #222; }}\
+         table {{ border-collapse: collapse; }}\
+         th, td {{ padding: 4px 10px; text-align: right; border-bottom: 1px solid #ddd; }}\
+         th:first-child, td:first-child {{ text-align: left; }}\
+         svg {{ display: block; margin: 1em 0 2em; }}\
+         </style></head><body>\n<h1>{title}</h1>\n",
+        title = escape(title)
+    );
+
+    html += &summary_table(runs);
+    html += &environment_table(runs);
+
+    html += "<h2>Frame time by monster count</h2>\n\
+             <p>Median (solid) and 99th percentile (dashed) frame time of every frame with that \
+             many monsters alive.</p>\n";
+    html += &line_chart("monsters", "frame time (ms)", &frame_time_by_monsters(runs));
+
+    html += "<h2>Time per system</h2>\n";
+    match system_breakdown(runs) {
+        Some(chart) => html += &chart,
+        None => html += "<p>No run was profiled; record with <code>--profile-systems</code>.</p>\n",
+    }
+
+    html += "<h2>Memory over time</h2>\n";
+    let memory = memory_over_time(runs);
+    if memory.is_empty() {
+        html += "<p>No run reported its memory use.</p>\n";
+    } else {
+        html += &line_chart("simulated seconds", "resident memory (MB)", &memory);
+    }
+
+    html += "</body></html>\n";
+    html
+}
+
+fn summary_table(runs: &[Run]) -> String {
+    let mut html = String::from(
+        "<table><tr><th>run</th><th>frames used</th><th>seconds</th><th>median ms</th>\
+         <th>p99 ms</th><th>peak monsters</th><th>peak memory</th></tr>\n",
+    );
+
+    for run in runs {
+        let times = stats::sorted(run.summary.iter().map(|frame| frame.frame_time_ms));
+        let seconds = run.frames.last().map_or(0., |frame| frame.elapsed);
+        let monsters = run.frames.iter().map(|frame| frame.monsters).max();
+        let memory = run
+            .frames
+            .iter()
+            .filter_map(|frame| frame.memory_bytes)
+            .max();
+
+        let _ = writeln!(
+            html,
+            "<tr><td><span style=\"color: {}\">&#9632;</span> {}</td><td>{} of {}</td>\
+             <td>{:.1}</td><td>{:.2}</td><td>{:.2}</td><td>{}</td><td>{}</td></tr>",
+            run.color,
+            escape(&run.label),
+            run.summary.len(),
+            run.frames.len(),
+            seconds,
+            stats::median(&times),
+            stats::percentile(&times, 99.),
+            monsters.unwrap_or(0),
+            memory.map_or("-".to_string(), |bytes| format!(
+                "{:.0} MB",
+                megabytes(bytes)
+            )),
+        );
+    }
+
+    html + "</table>\n"
+}
+
+fn environment_table(runs: &[Run]) -> String {
+    let fields: [(&str, fn(&Environment) -> String); 10] = [
+        ("CPU", |env| env.cpu.clone().unwrap_or_default()),
+        ("cores / threads", |env| {
+            let count = |count: Option<usize>| count.map_or("?".to_string(), |n| n.to_string());
+            format!("{} / {}", count(env.cores), count(env.threads))
+        }),
+        ("RAM", |env| {
+            env.memory_bytes.map_or(String::new(), |bytes| {
+                format!("{:.1} GB", megabytes(bytes) / 1024.)
+            })
+        }),
+        ("OS", |env| {
+            format!("{} {}", env.os, env.kernel.clone().unwrap_or_default())
+        }),
+        ("rustc", |env| env.rustc.clone()),
+        ("profile", |env| {
+            format!("{} (opt-level {})", env.profile, env.opt_level)
+        }),
+        ("commit", |env| env.git_commit.clone()),
+        ("features", |env| env.features.join(", ")),
+        ("arguments", |env| {
+            env.args
+                .iter()
+                .skip(1)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" ")
+        }),
+        ("scenario", |env| env.scenario.clone()),
+        ("seed", |env| {
+            env.seed.map(|seed| seed.to_string()).unwrap_or_default()
+        }),
+    ];
+
+    let mut html = String::from("<h2>Environment</h2>\n<table><tr><th></th>");
+    for run in runs {
+        let _ = write!(html, "<th>{}</th>", escape(&run.label));
+    }
+    html += "</tr>\n";
+
+    for (name, field) in fields {
+        let _ = write!(html, "<tr><td>{}</td>", name);
+        for run in runs {
+            let value = run
+                .environment
+                .as_ref()
+                .map_or("not recorded".to_string(), field);
+            let _ = write!(html, "<td><small>{}</small></td>", escape(&value));
+        }
+        html += "</tr>\n";
+    }
+
+    html + "</table>\n"
+}
+
+fn frame_time_by_monsters(runs: &[Run]) -> Vec<Line> {
+    let max_monsters = runs
+        .iter()
+        .flat_map(|run| run.frames.iter().map(|frame| frame.monsters))
+        .max()
+        .unwrap_or(0);
+    let bucket = (max_monsters / MONSTER_BUCKETS).max(1);
+
+    let mut lines = Vec::new();
+    for run in runs {
+        let mut buckets: BTreeMap<u32, Vec<f64>> = BTreeMap::new();
+        for frame in &run.summary {
+            buckets
+                .entry(frame.monsters / bucket * bucket)
+                .or_default()
+                .push(frame.frame_time_ms);
+        }
+
+        let (mut median, mut p99) = (Vec::new(), Vec::new());
+        for (monsters, times) in buckets {
+            let times = stats::sorted(times);
+            median.push((monsters as f64, stats::median(&times)));
+            p99.push((monsters as f64, stats::percentile(&times, 99.)));
+        }
+
+        lines.push(Line {
+            name: format!("{} median", run.label),
+            color: run.color,
+            dashed: false,
+            points: median,
+        });
+        lines.push(Line {
+            name: format!("{} p99", run.label),
+            color: run.color,
+            dashed: true,
+            points: p99,
+        });
+    }
+    lines
+}
+
+fn memory_over_time(runs: &[Run]) -> Vec<Line> {
+    runs.iter()
+        .filter_map(|run| {
+            let points: Vec<(f64, f64)> = run
+                .frames
+                .iter()
+                .filter_map(|frame| Some((frame.elapsed, megabytes(frame.memory_bytes?))))
+                .collect();
+            if points.is_empty() {
+                return None;
+            }
+
+            Some(Line {
+                name: run.label.clone(),
+                color: run.color,
+                dashed: false,
+                points: downsample(points),
+            })
+        })
+        .collect()
+}
+
+// Mean milliseconds per frame of the slowest systems, one bar per run
+fn system_breakdown(runs: &[Run]) -> Option<String> {
+    let means: Vec<BTreeMap<String, f64>> = runs
+        .iter()
+        .map(|run| {
+            let profiled = run.summary.iter().filter(|frame| !frame.systems.is_empty());
+            let mut totals: BTreeMap<String, f64> = BTreeMap::new();
+            let mut count = 0;
+            for frame in profiled {
+                count += 1;
+                for (name, ms) in &frame.systems {
+                    // Stages contain their systems, so leave them out
+                    if !name.starts_with("stage ") {
+                        *totals.entry(short_system_name(name)).or_default() += ms;
+                    }
+                }
+            }
+            totals
+                .values_mut()
+                .for_each(|total| *total /= count.max(1) as f64);
+            totals
+        })
+        .collect();
+
+    let mut slowest: Vec<(&String, f64)> = means
+        .iter()
+        .flatten()
+        .map(|(name, ms)| (name, *ms))
+        .collect();
+    slowest.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut systems: Vec<&String> = Vec::new();
+    for (name, _) in slowest {
+        if !systems.contains(&name) {
+            systems.push(name);
+        }
+    }
+    systems.truncate(TOP_SYSTEMS);
+    if systems.is_empty() {
+        return None;
+    }
+
+    let max = means
+        .iter()
+        .flat_map(|run| run.values().copied())
+        .fold(0., f64::max);
+    let bar_height = 12.;
+    let group_height = bar_height * runs.len() as f64 + 8.;
+    let label_width = 220.;
+    let plot_width = WIDTH - label_width - MARGIN_RIGHT;
+    let height = MARGIN_TOP + group_height * systems.len() as f64 + MARGIN_BOTTOM;
+    let step = nice_step(max);
+    let axis_max = (max / step).ceil().max(1.) * step;
+    let scale = |ms: f64| label_width + ms / axis_max * plot_width;
+
+    let mut svg = svg_open(height);
+    for tick in ticks(axis_max, step) {
+        let x = scale(tick);
+        let _ = write!(
+            svg,
+            "<line x1=\"{x:.1}\" y1=\"{top}\" x2=\"{x:.1}\" y2=\"{bottom:.1}\" stroke=\"#eee\"/>\
+             <text x=\"{x:.1}\" y=\"{label:.1}\" text-anchor=\"middle\">{tick}</text>",
+            top = MARGIN_TOP,
+            bottom = height - MARGIN_BOTTOM,
+            label = height - MARGIN_BOTTOM + 16.,
+            tick = format_tick(tick),
+        );
+    }
+    let _ = write!(
+        svg,
+        "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">mean ms per frame</text>",
+        label_width + plot_width / 2.,
+        height - 8.
+    );
+
+    for (i, system) in systems.iter().enumerate() {
+        let top = MARGIN_TOP + group_height * i as f64;
+        let _ = write!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            label_width - 8.,
+            top + group_height / 2. + 4.,
+            escape(system)
+        );
+        for (j, (run, run_means)) in runs.iter().zip(&means).enumerate() {
+            let ms = run_means.get(*system).copied().unwrap_or(0.);
+            let _ = write!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{}\" fill=\"{}\">\
+                 <title>{}: {:.3} ms</title></rect>",
+                label_width,
+                top + 4. + bar_height * j as f64,
+                scale(ms) - label_width,
+                bar_height - 2.,
+                run.color,
+                escape(&run.label),
+                ms
+            );
+        }
+    }
+    legend(
+        &mut svg,
+        runs.iter()
+            .map(|run| (run.label.as_str(), run.color, false)),
+    );
+
+    Some(svg + "</svg>\n")
+}
+
+// `bevy_rapier3d::plugin::systems::step_simulation<()>` reads better as `step_simulation`
+fn short_system_name(name: &str) -> String {
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name).to_string()
+}
+
+fn line_chart(x_label: &str, y_label: &str, lines: &[Line]) -> String {
+    let points = || lines.iter().flat_map(|line| line.points.iter());
+    let x_min = points().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let x_max = points().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+    let y_max = points().map(|p| p.1).fold(0., f64::max);
+    if !x_min.is_finite() {
+        return "<p>No data.</p>\n".to_string();
+    }
+
+    let (x_step, y_step) = (nice_step(x_max - x_min), nice_step(y_max));
+    let x_min = (x_min / x_step).floor() * x_step;
+    let x_max = ((x_max / x_step).ceil() * x_step).max(x_min + x_step);
+    let y_max = ((y_max / y_step).ceil() * y_step).max(y_step);
+
+    let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let x = |value: f64| MARGIN_LEFT + (value - x_min) / (x_max - x_min) * plot_width;
+    let y = |value: f64| HEIGHT - MARGIN_BOTTOM - value / y_max * plot_height;
+
+    let mut svg = svg_open(HEIGHT);
+    for tick in ticks(y_max, y_step) {
+        let _ = write!(
+            svg,
+            "<line x1=\"{left}\" y1=\"{y:.1}\" x2=\"{right}\" y2=\"{y:.1}\" stroke=\"#eee\"/>\
+             <text x=\"{label}\" y=\"{text_y:.1}\" text-anchor=\"end\">{tick}</text>",
+            left = MARGIN_LEFT,
+            right = WIDTH - MARGIN_RIGHT,
+            y = y(tick),
+            label = MARGIN_LEFT - 6.,
+            text_y = y(tick) + 4.,
+            tick = format_tick(tick),
+        );
+    }
+    for tick in ticks(x_max - x_min, x_step).map(|tick| tick + x_min) {
+        let _ = write!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            x(tick),
+            HEIGHT - MARGIN_BOTTOM + 16.,
+            format_tick(tick)
+        );
+    }
+    let _ = write!(
+        svg,
+        "<line x1=\"{left}\" y1=\"{bottom}\" x2=\"{right}\" y2=\"{bottom}\" stroke=\"#888\"/>\
+         <line x1=\"{left}\" y1=\"{top}\" x2=\"{left}\" y2=\"{bottom}\" stroke=\"#888\"/>\
+         <text x=\"{x_mid:.1}\" y=\"{x_label_y:.1}\" text-anchor=\"middle\">{x_label}</text>\
+         <text transform=\"translate(14 {y_mid:.1}) rotate(-90)\" text-anchor=\"middle\">{y_label}</text>",
+        left = MARGIN_LEFT,
+        right = WIDTH - MARGIN_RIGHT,
+        top = MARGIN_TOP,
+        bottom = HEIGHT - MARGIN_BOTTOM,
+        x_mid = MARGIN_LEFT + plot_width / 2.,
+        x_label_y = HEIGHT - 8.,
+        y_mid = MARGIN_TOP + plot_height / 2.,
+        x_label = escape(x_label),
+        y_label = escape(y_label),
+    );
+
+    for line in lines {
+        let path: Vec<String> = line
+            .points
+            .iter()
+            .map(|&(px, py)| format!("{:.1},{:.1}", x(px), y(py)))
+            .collect();
+        let _ = write!(
+            svg,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"{}/>",
+            path.join(" "),
+            line.color,
+            if line.dashed {
+                " stroke-dasharray=\"4 3\""
+            } else {
+                ""
+            }
+        );
+    }
+    legend(
+        &mut svg,
+        lines
+            .iter()
+            .map(|line| (line.name.as_str(), line.color, line.dashed)),
+    );
+
+    svg + "</svg>\n"
+}
+
+fn svg_open(height: f64) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\" font-size=\"11\">",
+        w = WIDTH,
+        h = height
+    )
+}
+
+fn legend<'a>(svg: &mut String, entries: impl Iterator<Item = (&'a str, &'a str, bool)>) {
+    let left = WIDTH - MARGIN_RIGHT + 12.;
+    for (i, (name, color, dashed)) in entries.enumerate() {
+        let y = MARGIN_TOP + 16. * i as f64;
+        let _ = write!(
+            svg,
+            "<line x1=\"{left}\" y1=\"{y}\" x2=\"{end}\" y2=\"{y}\" stroke=\"{color}\" \
+             stroke-width=\"2\"{dash}/><text x=\"{text}\" y=\"{text_y}\">{name}</text>",
+            end = left + 18.,
+            dash = if dashed {
+                " stroke-dasharray=\"4 3\""
+            } else {
+                ""
+            },
+            text = left + 24.,
+            text_y = y + 4.,
+            name = escape(name),
+        );
+    }
+}
+
+/// A round step that splits `range` into about five ticks.
+fn nice_step(range: f64) -> f64 {
+    if range <= 0. || !range.is_finite() {
+        return 1.;
+    }
+
+    let raw = range / 5.;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    [1., 2., 5., 10.]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= raw)
+        .unwrap_or(10. * magnitude)
+}
+
+fn ticks(max: f64, step: f64) -> impl Iterator<Item = f64> {
+    let count = (max / step).ceil().max(1.) as usize;
+    (0..=count).map(move |i| i as f64 * step)
+}
+
+fn format_tick(value: f64) -> String {
+    if value.fract().abs() < 1e-9 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.2}", value).trim_end_matches('0').to_string()
+    }
+}
+
+fn downsample(points: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    let step = (points.len() + MAX_POINTS - 1) / MAX_POINTS;
+    if step <= 1 {
+        return points;
+    }
+    points.into_iter().step_by(step).collect()
+}
+
+fn megabytes(bytes: u64) -> f64 {
+    bytes as f64 / (1024. * 1024.)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,69 @@
+//! Runs the same seeded scenario twice, each in a fresh headless app, and reports the first tick
+//! where the world hashes differ.
+//!
+//! Usage: `verify_determinism [scenario args...]`. A seed, a fixed timestep and a tick limit are
+//! added when not given, since determinism can only be checked with all three.
+
+use std::{env, process};
+
+use stress_bevy::{
+    headless::run_headless,
+    metrics::{first_divergence, Divergence},
+    scenario::Scenario,
+};
+
+const DEFAULT_SEED: u64 = 1;
+const DEFAULT_TICKS: u64 = 1200;
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let has = |args: &[String], name: &str| {
+        args.iter()
+            .any(|arg| arg == name || arg.starts_with(&format!("{}=", name)))
+    };
+
+    if !has(&args, "--seed") {
+        args.push(format!("--seed={}", DEFAULT_SEED));
+    }
+    if !has(&args, "--fixed-timestep") {
+        args.push("--fixed-timestep".to_string());
+    }
+    if !has(&args, "--ticks") {
+        args.push(format!("--ticks={}", DEFAULT_TICKS));
+    }
+    if has(&args, "--metrics") {
+        eprintln!("--metrics is set by the verifier");
+        process::exit(2);
+    }
+    // Its subscriber is global, so only the first run could have it
+    if has(&args, "--profile-systems") || has(&args, "--chrome-trace") {
+        eprintln!("system profiling can't be used with in-process runs");
+        process::exit(2);
+    }
+
+    let scenario = Scenario::parse(args.clone()).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(2);
+    });
+
+    let runs = [run_headless(scenario.clone()), run_headless(scenario)];
+
+    match first_divergence(&runs[0].frames, &runs[1].frames) {
+        None => println!(
+            "deterministic: {} ticks matched ({})",
+            runs[0].frames.len(),
+            args.join(" ")
+        ),
+        Some(Divergence::Hash { tick, a, b }) => {
+            println!("diverged at tick {}: {:016x} != {:016x}", tick, a, b);
+            process::exit(1);
+        }
+        Some(Divergence::Length { a, b }) => {
+            println!(
+                "hashes matched, but the runs recorded {} and {} ticks",
+                a, b
+            );
+            process::exit(1);
+        }
+    }
+}
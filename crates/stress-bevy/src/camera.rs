@@ -0,0 +1,139 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{input::mouse::MouseMotion, prelude::*};
+
+use crate::{
+    aiming::update_aim,
+    character::{move_character, Character},
+    combat::launch_projectile,
+    hitscan::fire_hitscan,
+    simulation::SimulationClock,
+};
+
+const START_EYE: Vec3 = Vec3::new(-2.0, 5.0, 5.0);
+const START_TARGET: Vec3 = Vec3::new(0., 0., 0.);
+/// Radians the camera orbits per pixel of mouse motion, per second of simulation.
+const MOUSE_ROTATE_SENSITIVITY: f32 = 0.08;
+/// Just short of straight up or down, where yaw stops meaning anything.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+/// Simulated seconds the camera takes to close most (1 - 1/e) of the gap to its `LookTransform`.
+const SMOOTHING_SECONDS: f32 = 0.075;
+
+/// The camera the character walks and aims relative to.
+#[derive(Component)]
+pub struct GameCamera;
+
+/// Where the camera should be and what it should look at. Gameplay moves these, and the camera
+/// eases toward them.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct LookTransform {
+    pub eye: Vec3,
+    pub target: Vec3,
+}
+
+/// Where the camera actually is, trailing its `LookTransform`. Eased by simulated time, so it
+/// lands in the same place with or without the renderer and on replay.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct SmoothedLook(pub LookTransform);
+
+/// The orbit camera that follows the character, turned by the mouse. Placed the same way with or
+/// without the renderer, which only adds a view to it.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_cameras)
+            .add_system(
+                camera_input_map
+                    .before(move_character)
+                    .before(look_at_character)
+                    .before(launch_projectile)
+                    .before(fire_hitscan),
+            )
+            .add_system(look_at_character)
+            // Before aiming, so the crosshair ray leaves the camera as it's drawn this frame
+            .add_system(
+                place_camera
+                    .after(camera_input_map)
+                    .after(look_at_character)
+                    .before(update_aim),
+            );
+    }
+}
+
+fn spawn_cameras(mut commands: Commands) {
+    let look = LookTransform {
+        eye: START_EYE,
+        target: START_TARGET,
+    };
+    commands
+        .spawn_bundle(TransformBundle::default())
+        .insert_bundle((look, SmoothedLook(look), GameCamera));
+}
+
+pub fn place_camera(
+    mut cameras: Query<(&LookTransform, &mut SmoothedLook, &mut Transform), With<GameCamera>>,
+    clock: Res<SimulationClock>,
+) {
+    let blend = 1. - (-clock.dt / SMOOTHING_SECONDS).exp();
+
+    for (look, mut smoothed, mut transform) in cameras.iter_mut() {
+        let SmoothedLook(current) = &mut *smoothed;
+        current.eye = current.eye.lerp(look.eye, blend);
+        current.target = current.target.lerp(look.target, blend);
+
+        *transform = Transform::from_translation(current.eye).looking_at(current.target, Vec3::Y);
+    }
+}
+
+pub fn look_at_character(
+    mut cameras: Query<&mut LookTransform, With<GameCamera>>,
+    character: Query<&Transform, (Changed<Transform>, With<Character>)>,
+) {
+    if character.is_empty() {
+        return;
+    }
+
+    for mut look in cameras.iter_mut() {
+        if let Ok(target) = character.get_single() {
+            look.target = target.translation + Vec3::Y * 4.;
+
+            let line = (look.eye - target.translation).normalize();
+            look.eye = target.translation + line * 20.;
+        }
+    }
+}
+
+/// Orbits the eye around the target with the mouse, keeping its distance.
+pub fn camera_input_map(
+    mut mouse_motion_events: EventReader<MouseMotion>,
+    clock: Res<SimulationClock>,
+    mut cameras: Query<&mut LookTransform, With<GameCamera>>,
+) {
+    let mut cursor_delta = Vec2::ZERO;
+    for event in mouse_motion_events.iter() {
+        cursor_delta += event.delta;
+    }
+    if cursor_delta == Vec2::ZERO {
+        return;
+    }
+    let delta = MOUSE_ROTATE_SENSITIVITY * clock.dt * cursor_delta;
+
+    for mut look in cameras.iter_mut() {
+        let offset = look.eye - look.target;
+        let radius = offset.length();
+        if radius == 0. {
+            continue;
+        }
+
+        let yaw = offset.x.atan2(offset.z) - delta.x;
+        let pitch = ((offset.y / radius).asin() + delta.y).clamp(-MAX_PITCH, MAX_PITCH);
+        look.eye = look.target
+            + radius
+                * Vec3::new(
+                    pitch.cos() * yaw.sin(),
+                    pitch.sin(),
+                    pitch.cos() * yaw.cos(),
+                );
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    camera::{GameCamera, LookTransform},
+    collision_layers::{CollisionMatrix, Layer},
+    loading::AppState,
+    simulation::SimulationClock,
+    weapons::Loadout,
+};
+
+#[derive(Component)]
+pub struct Character;
+
+pub struct CharacterAnimations {
+    pub idle: Handle<AnimationClip>,
+}
+
+/// The player's character, walked around with WASD relative to the camera.
+pub struct CharacterPlugin;
+
+impl Plugin for CharacterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_character)
+            .add_system_set(SystemSet::on_update(AppState::Playing).with_system(move_character));
+    }
+}
+
+// Just the body, the model is added by `VisualsPlugin` when there's one to draw
+fn spawn_character(mut commands: Commands, layers: Res<CollisionMatrix>) {
+    commands
+        .spawn_bundle(TransformBundle {
+            local: Transform::from_xyz(10.0, 0.2, 0.0).with_scale(Vec3::ONE * 0.3),
+            global: GlobalTransform::identity(),
+        })
+        .insert_bundle((
+            RigidBody::Dynamic,
+            Velocity::default(),
+            Collider::cuboid(2., 9., 2.),
+            Friction::coefficient(0.),
+            Character,
+            Loadout::default(),
+        ))
+        .insert_bundle(layers.bundle(Layer::Player))
+        .insert(LockedAxes::ROTATION_LOCKED_X | LockedAxes::ROTATION_LOCKED_Z)
+        .insert(Damping {
+            linear_damping: 0.5,
+            angular_damping: 1.0,
+        });
+}
+
+pub fn move_character(
+    mut character: Query<&mut Transform, (With<Character>, Changed<Transform>)>,
+    camera: Query<&LookTransform, With<GameCamera>>,
+    keyboard: Res<Input<KeyCode>>,
+    clock: Res<SimulationClock>,
+) {
+    let mut character = match character.get_single_mut() {
+        Ok(x) => x,
+        _ => return,
+    };
+    let camera = match camera.get_single() {
+        Ok(x) => x,
+        _ => return,
+    };
+
+    let direction = (character.translation - camera.eye).normalize() * Vec3::new(1., 0., 1.);
+    let direction_perp = {
+        let line = Vec2::new(direction.x, direction.z);
+        let perp = line.perp();
+
+        Vec3::new(perp.x, 0., perp.y)
+    };
+
+    let dest_rot = character
+        .looking_at(character.translation - direction, Vec3::Y)
+        .rotation;
+    character.rotation = character.rotation.lerp(dest_rot, 5. * clock.dt);
+
+    if !keyboard.is_changed() {
+        return;
+    }
+
+    let mut delta = Vec3::ZERO;
+
+    if keyboard.pressed(KeyCode::W) {
+        delta += direction;
+    }
+
+    if keyboard.pressed(KeyCode::S) {
+        delta -= direction;
+    }
+
+    if keyboard.pressed(KeyCode::D) {
+        delta += direction_perp;
+    }
+
+    if keyboard.pressed(KeyCode::A) {
+        delta -= direction_perp;
+    }
+
+    if delta == Vec3::ZERO {
+        return;
+    }
+
+    character.translation = character
+        .translation
+        .lerp(character.translation + delta.normalize(), clock.dt * 10.);
+}
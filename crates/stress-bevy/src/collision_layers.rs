@@ -0,0 +1,125 @@
+use std::str::FromStr;
+
+use bevy_rapier3d::{prelude::*, rapier::geometry::InteractionGroups};
+
+/// Named collision layers, one rapier group bit each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Player,
+    Monster,
+    /// Projectiles that deal damage on a direct hit.
+    Projectile,
+    Ground,
+    /// Projectiles that explode on whatever they touch first.
+    Explosive,
+}
+
+const LAYERS: usize = 5;
+
+impl Layer {
+    pub fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+impl FromStr for Layer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "player" => Ok(Layer::Player),
+            "monster" => Ok(Layer::Monster),
+            "projectile" => Ok(Layer::Projectile),
+            "ground" => Ok(Layer::Ground),
+            "explosive" => Ok(Layer::Explosive),
+            _ => Err(format!("unknown collision layer '{}'", s)),
+        }
+    }
+}
+
+/// Which layers detect each other (`collision`) and which push each other apart (`solver`).
+/// Both matrices are kept symmetric.
+///
+/// Projectiles report every collision, so by default they only collide where something handles
+/// it: plain ones with monsters, explosives with monsters and the ground. The rest fall through
+/// the ground and are retired, unless `--collide=projectile:ground` is given.
+#[derive(Debug, Clone)]
+pub struct CollisionMatrix {
+    collision: [u32; LAYERS],
+    solver: [u32; LAYERS],
+}
+
+impl Default for CollisionMatrix {
+    fn default() -> Self {
+        use Layer::*;
+
+        let mut matrix = Self {
+            collision: [0; LAYERS],
+            solver: [0; LAYERS],
+        };
+        for (a, b) in [
+            (Player, Monster),
+            (Player, Ground),
+            (Monster, Monster),
+            (Monster, Projectile),
+            (Monster, Ground),
+            (Monster, Explosive),
+            (Explosive, Ground),
+        ] {
+            matrix.set_collision(a, b, true);
+            matrix.set_solver(a, b, true);
+        }
+        matrix
+    }
+}
+
+impl CollisionMatrix {
+    pub fn set_collision(&mut self, a: Layer, b: Layer, enabled: bool) {
+        set_pair(&mut self.collision, a, b, enabled);
+        // Pairs that never touch can't exchange contact forces either
+        if !enabled {
+            set_pair(&mut self.solver, a, b, false);
+        }
+    }
+
+    pub fn set_solver(&mut self, a: Layer, b: Layer, enabled: bool) {
+        set_pair(&mut self.solver, a, b, enabled);
+    }
+
+    pub fn collision_groups(&self, layer: Layer) -> CollisionGroups {
+        CollisionGroups::new(layer.bit(), self.collision[layer as usize])
+    }
+
+    pub fn solver_groups(&self, layer: Layer) -> SolverGroups {
+        SolverGroups::new(layer.bit(), self.solver[layer as usize])
+    }
+
+    /// Groups for scene queries (ray casts, overlap tests) made on behalf of `layer`.
+    pub fn query_groups(&self, layer: Layer) -> InteractionGroups {
+        InteractionGroups::new(layer.bit(), self.collision[layer as usize])
+    }
+
+    /// Groups for rays and blasts on behalf of the weapons: anything either kind of projectile
+    /// collides with.
+    pub fn shot_query_groups(&self) -> InteractionGroups {
+        let (plain, explosive) = (Layer::Projectile, Layer::Explosive);
+        InteractionGroups::new(
+            plain.bit() | explosive.bit(),
+            self.collision[plain as usize] | self.collision[explosive as usize],
+        )
+    }
+
+    pub fn bundle(&self, layer: Layer) -> (CollisionGroups, SolverGroups) {
+        (self.collision_groups(layer), self.solver_groups(layer))
+    }
+}
+
+fn set_pair(matrix: &mut [u32; LAYERS], a: Layer, b: Layer, enabled: bool) {
+    if enabled {
+        matrix[a as usize] |= b.bit();
+        matrix[b as usize] |= a.bit();
+    } else {
+        matrix[a as usize] &= !b.bit();
+        matrix[b as usize] &= !a.bit();
+    }
+}
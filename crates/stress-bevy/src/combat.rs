@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    aiming::{update_aim, Aim},
+    archetypes::Health,
+    camera::place_camera,
+    character::Character,
+    collision_layers::{CollisionMatrix, Layer},
+    explosions::detonate_explosives,
+    hitscan::fire_hitscan,
+    loading::AppState,
+    pooling::ProjectilePool,
+    scenario::Scenario,
+    simulation::{RngStream, SimulationClock, SimulationRng},
+    weapons::{apply_damage, switch_weapon, Damage, Explosion, Loadout, WeaponKind},
+};
+
+const MUZZLE_HEIGHT: f32 = 1.5;
+/// Seconds a projectile that missed keeps flying or rolling before it's retired.
+const PROJECTILE_LIFETIME: f64 = 10.;
+
+#[derive(Component)]
+pub struct HitDetection;
+
+#[derive(Component)]
+pub struct Projectile;
+
+/// Simulation time a projectile was fired at.
+#[derive(Component)]
+pub struct Launched(pub f64);
+
+/// Aiming through the crosshair, and the weapons fired along it.
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Aim>()
+            // Aims from where the camera was placed this frame
+            .add_system(
+                update_aim
+                    .after(place_camera)
+                    .before(launch_projectile)
+                    .before(fire_hitscan),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Playing)
+                    .with_system(switch_weapon.before(launch_projectile).before(fire_hitscan))
+                    .with_system(launch_projectile)
+                    .with_system(fire_hitscan)
+                    .with_system(detect_projectile_collision)
+                    .with_system(retire_spent_projectiles.after(detect_projectile_collision))
+                    .with_system(
+                        detonate_explosives
+                            .after(detect_projectile_collision)
+                            .after(fire_hitscan),
+                    ),
+            );
+    }
+}
+
+pub fn detect_projectile_collision(
+    mut detection: Query<Option<&mut Health>, With<HitDetection>>,
+    projectiles: Query<&Damage, (With<Projectile>, Without<Explosion>)>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut pool: ResMut<ProjectilePool>,
+    mut commands: Commands,
+) {
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(a, b, _flags) = collision_event {
+            let (target, projectile) = if projectiles.get(*a).is_ok() {
+                (*b, *a)
+            } else {
+                (*a, *b)
+            };
+
+            if let (Ok(health), Ok(&Damage(damage))) =
+                (detection.get_mut(target), projectiles.get(projectile))
+            {
+                if let Some(mut health) = health {
+                    apply_damage(&mut commands, target, &mut health, damage);
+                }
+                pool.retire(&mut commands, projectile);
+            }
+        }
+    }
+}
+
+/// Retires projectiles that missed, once they've left the arena or outlived
+/// `PROJECTILE_LIFETIME`, so they don't pile up rolling around on the ground.
+pub fn retire_spent_projectiles(
+    projectiles: Query<(Entity, &Transform, &Launched), With<Projectile>>,
+    scenario: Res<Scenario>,
+    clock: Res<SimulationClock>,
+    mut pool: ResMut<ProjectilePool>,
+    mut commands: Commands,
+) {
+    let arena_half = scenario.arena_size / 2.;
+    for (entity, transform, &Launched(at)) in projectiles.iter() {
+        let position = transform.translation;
+        let outside = position.x.abs() > arena_half
+            || position.z.abs() > arena_half
+            || position.y < -arena_half;
+        if outside || clock.elapsed - at > PROJECTILE_LIFETIME {
+            pool.retire(&mut commands, entity);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn launch_projectile(
+    mut character: Query<(&Transform, &mut Loadout), With<Character>>,
+    aim: Res<Aim>,
+    scenario: Res<Scenario>,
+    layers: Res<CollisionMatrix>,
+    mouse_button: Res<Input<MouseButton>>,
+    clock: Res<SimulationClock>,
+    sim_rng: Res<SimulationRng>,
+    mut pool: ResMut<ProjectilePool>,
+    mut commands: Commands,
+) {
+    let (character, mut loadout) = match character.get_single_mut() {
+        Ok(x) => x,
+        _ => return,
+    };
+
+    let projectile = match loadout.weapon().kind {
+        WeaponKind::Projectile(projectile) => projectile,
+        _ => return,
+    };
+
+    if !loadout.try_fire(&mouse_button, clock.elapsed) {
+        return;
+    }
+    let weapon = loadout.weapon();
+
+    // Fire from the muzzle toward whatever is under the crosshair, not along the camera's line
+    let muzzle = character.translation + Vec3::Y * MUZZLE_HEIGHT;
+    let aim = aim.direction_from(muzzle);
+    let pos = muzzle + aim * 2.;
+    let rng = sim_rng.stream(RngStream::Projectiles, clock.tick);
+    let layer = match projectile.explosion {
+        Some(_) => Layer::Explosive,
+        None => Layer::Projectile,
+    };
+
+    for _ in 0..weapon.projectiles_per_shot {
+        let direction = weapon.spread_direction(aim, &rng);
+
+        let mut entity = match pool.take() {
+            // Parked without gravity
+            Some(parked) => {
+                let mut entity = commands.entity(parked);
+                entity.insert(GravityScale(1.));
+                entity
+            }
+            None => commands.spawn(),
+        };
+        if let Some(explosion) = projectile.explosion {
+            entity.insert(explosion);
+        }
+        if scenario.ccd {
+            entity.insert(Ccd::enabled());
+        }
+        entity
+            .insert(RigidBody::Dynamic)
+            .insert(Velocity {
+                linvel: direction * projectile.speed,
+                angvel: Vec3::ZERO,
+            })
+            .insert_bundle(TransformBundle::from(Transform::from_translation(pos)))
+            .insert_bundle((
+                Collider::ball(projectile.radius),
+                Projectile,
+                projectile,
+                Launched(clock.elapsed),
+                Damage(projectile.damage),
+                ActiveEvents::COLLISION_EVENTS,
+            ))
+            .insert_bundle(layers.bundle(layer));
+    }
+}
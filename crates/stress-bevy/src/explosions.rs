@@ -0,0 +1,97 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    collision_layers::CollisionMatrix,
+    archetypes::Health,
+    pooling::ProjectilePool,
+    weapons::{apply_damage, Explosion},
+    combat::HitDetection,
+};
+
+/// Blows up explosive projectiles on their first collision, damaging and pushing away every
+/// `HitDetection` entity inside the blast radius. Runs after the other damage systems, so it sees
+/// the targets they killed.
+pub fn detonate_explosives(
+    explosives: Query<(&Explosion, &Transform)>,
+    mut targets: Query<(&Transform, Option<&mut Health>), With<HitDetection>>,
+    rapier_context: Res<RapierContext>,
+    layers: Res<CollisionMatrix>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut pool: ResMut<ProjectilePool>,
+    mut commands: Commands,
+) {
+    let mut detonated = HashSet::default();
+    let mut caught = Vec::new();
+    // Inserting the component replaces it, so blasts catching the same target add up first
+    let mut impulses: HashMap<Entity, Vec3> = HashMap::default();
+
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(a, b, _flags) = collision_event {
+            for &projectile in [a, b] {
+                let (explosion, transform) = match explosives.get(projectile) {
+                    Ok(x) => x,
+                    _ => continue,
+                };
+                // A projectile touching several colliders at once still only explodes once
+                if !detonated.insert(projectile) {
+                    continue;
+                }
+                pool.retire(&mut commands, projectile);
+
+                let center = transform.translation;
+                caught.clear();
+                rapier_context.intersections_with_shape(
+                    center,
+                    Quat::IDENTITY,
+                    &Collider::ball(explosion.radius),
+                    layers.shot_query_groups(),
+                    None,
+                    |entity| {
+                        caught.push(entity);
+                        true
+                    },
+                );
+
+                for &target in caught.iter() {
+                    let (target_transform, health) = match targets.get_mut(target) {
+                        Ok(x) => x,
+                        _ => continue,
+                    };
+
+                    // Already killed by an earlier blast this frame, and queued for despawn
+                    if matches!(&health, Some(health) if health.0 <= 0.) {
+                        continue;
+                    }
+
+                    let offset = target_transform.translation - center;
+                    let falloff = (1. - offset.length() / explosion.radius).clamp(0., 1.);
+
+                    *impulses.entry(target).or_default() +=
+                        offset.normalize_or_zero() * explosion.impulse * falloff;
+                    if let Some(mut health) = health {
+                        apply_damage(&mut commands, target, &mut health, explosion.damage * falloff);
+                    }
+                }
+            }
+        }
+    }
+
+    for (target, impulse) in impulses {
+        // Killed by a later blast or by a hit this frame, and queued for despawn
+        let alive = matches!(
+            targets.get(target),
+            Ok((_, health)) if health.map_or(true, |health| health.0 > 0.)
+        );
+        if !alive {
+            continue;
+        }
+        commands.entity(target).insert(ExternalImpulse {
+            impulse,
+            torque_impulse: Vec3::ZERO,
+        });
+    }
+}
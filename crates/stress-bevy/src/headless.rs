@@ -0,0 +1,71 @@
+use bevy::{
+    app::{AppExit, PluginGroupBuilder, ScheduleRunnerPlugin},
+    ecs::event::ManualEventReader,
+    log::LogPlugin,
+    prelude::*,
+};
+#[cfg(feature = "render")]
+use bevy::{
+    render::{settings::WgpuSettings, RenderPlugin},
+    winit::WinitPlugin,
+};
+
+use crate::{metrics::MetricsFile, recorder::MetricsRecorder, scenario::Scenario, GamePlugins};
+
+/// `DefaultPlugins` without a window or GPU, looping as fast as it can. With the `render` feature
+/// the renderer is never set up, but its plugins still register the assets and components scenes
+/// are made of. Without it they aren't compiled at all.
+pub struct HeadlessPlugins;
+
+impl PluginGroup for HeadlessPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        DefaultPlugins.build(group);
+        #[cfg(feature = "render")]
+        group
+            .disable::<WinitPlugin>()
+            .add_before::<RenderPlugin, _>(NoBackendsPlugin);
+        group.add(ScheduleRunnerPlugin::default());
+    }
+}
+
+/// Plays `scenario` in a fresh headless app in this process until it exits, and returns the
+/// metrics it recorded. Needs `Scenario::ticks`, since nothing else ends the run. Logging is left
+/// out, since only the first app in a process could install it.
+pub fn run_headless(scenario: Scenario) -> MetricsFile {
+    assert!(
+        scenario.ticks.is_some(),
+        "a headless run needs a tick limit"
+    );
+
+    let mut app = App::new();
+    app.insert_resource(scenario.collision.clone())
+        .insert_resource(scenario)
+        .add_plugins_with(HeadlessPlugins, |group| group.disable::<LogPlugin>())
+        .add_plugins(GamePlugins);
+
+    // What the schedule runner does, without handing the app over
+    let mut exits = ManualEventReader::<AppExit>::default();
+    loop {
+        app.update();
+        let events = app.world.resource::<Events<AppExit>>();
+        if exits.iter(events).next().is_some() {
+            break;
+        }
+    }
+
+    std::mem::take(&mut app.world.resource_mut::<MetricsRecorder>().file)
+}
+
+// The renderer reads its settings when it's built, so they go in just before it
+#[cfg(feature = "render")]
+struct NoBackendsPlugin;
+
+#[cfg(feature = "render")]
+impl Plugin for NoBackendsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WgpuSettings {
+            backends: None,
+            ..default()
+        });
+    }
+}
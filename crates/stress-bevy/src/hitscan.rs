@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    collision_layers::CollisionMatrix,
+    simulation::{RngStream, SimulationClock, SimulationRng},
+    aiming::Aim,
+    archetypes::Health,
+    weapons::{apply_damage, Loadout, WeaponKind},
+    character::Character,
+    combat::{HitDetection, Projectile},
+};
+
+/// Fires hitscan weapons by casting rays from the camera through the crosshair, damaging
+/// `HitDetection` entities in order until the ray runs out of pierce or hits something else.
+#[allow(clippy::too_many_arguments)]
+pub fn fire_hitscan(
+    mut character: Query<(Entity, &mut Loadout), With<Character>>,
+    aim: Res<Aim>,
+    mut targets: Query<Option<&mut Health>, With<HitDetection>>,
+    projectiles: Query<Entity, With<Projectile>>,
+    rapier_context: Res<RapierContext>,
+    layers: Res<CollisionMatrix>,
+    mouse_button: Res<Input<MouseButton>>,
+    clock: Res<SimulationClock>,
+    sim_rng: Res<SimulationRng>,
+    mut commands: Commands,
+) {
+    let (character, mut loadout) = match character.get_single_mut() {
+        Ok(x) => x,
+        _ => return,
+    };
+
+    let hitscan = match loadout.weapon().kind {
+        WeaponKind::Hitscan(hitscan) => hitscan,
+        _ => return,
+    };
+
+    if !loadout.try_fire(&mouse_button, clock.elapsed) {
+        return;
+    }
+    let weapon = loadout.weapon();
+
+    let rng = sim_rng.stream(RngStream::Hitscan, clock.tick);
+    // Rays start at the camera, so skip the character and our own projectiles in between
+    let filter = |entity: Entity| entity != character && projectiles.get(entity).is_err();
+    let mut hits = Vec::new();
+
+    for _ in 0..weapon.projectiles_per_shot {
+        let direction = weapon.spread_direction(aim.direction, &rng);
+
+        hits.clear();
+        rapier_context.intersections_with_ray(
+            aim.origin,
+            direction,
+            hitscan.range,
+            true,
+            layers.shot_query_groups(),
+            Some(&filter),
+            |entity, intersection| {
+                hits.push((entity, intersection.toi));
+                true
+            },
+        );
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let mut pierced = 0;
+        for &(entity, _) in hits.iter() {
+            let health = match targets.get_mut(entity) {
+                Ok(x) => x,
+                // Walls and the ground stop the ray
+                _ => break,
+            };
+
+            if let Some(mut health) = health {
+                apply_damage(&mut commands, entity, &mut health, hitscan.damage);
+            }
+
+            if pierced == hitscan.pierce {
+                break;
+            }
+            pierced += 1;
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! The stress test game as a set of plugins, and the pieces its binaries share.
+
+use bevy::{app::PluginGroupBuilder, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+pub mod aiming;
+#[cfg(feature = "render")]
+pub mod animation;
+pub mod archetypes;
+pub mod arena;
+pub mod assets;
+pub mod autopilot;
+pub mod camera;
+pub mod character;
+pub mod collision_layers;
+pub mod combat;
+pub mod explosions;
+pub mod headless;
+pub mod hitscan;
+pub mod loading;
+pub mod matrix;
+pub mod metrics;
+#[cfg(feature = "render")]
+pub mod overlay;
+pub mod pooling;
+pub mod profiling;
+pub mod recorder;
+pub mod replay;
+pub mod scenario;
+pub mod simulation;
+pub mod stats;
+pub mod steady_state;
+#[cfg(test)]
+mod steady_state_tests;
+#[cfg(test)]
+mod tunneling_tests;
+#[cfg(feature = "render")]
+pub mod visuals;
+pub mod waves;
+pub mod weapons;
+
+/// Everything gameplay needs, physics included. Expects the `Scenario` and its
+/// `CollisionMatrix` as resources, and Bevy's own plugins, windowed or headless, added first.
+pub struct GamePlugins;
+
+impl PluginGroup for GamePlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group
+            .add(RapierPhysicsPlugin::<NoUserData>::default())
+            // Before the simulation, which reads the seed a replay restores
+            .add(replay::ReplayPlugin)
+            .add(simulation::SimulationPlugin)
+            .add(autopilot::AutopilotPlugin)
+            .add(recorder::RecorderPlugin)
+            .add(pooling::PoolingPlugin)
+            .add(assets::AssetManifestPlugin)
+            .add(archetypes::MonsterArchetypePlugin)
+            .add(loading::LoadingPlugin)
+            .add(arena::ArenaPlugin)
+            .add(camera::CameraPlugin)
+            .add(character::CharacterPlugin)
+            .add(waves::WavesPlugin)
+            .add(combat::CombatPlugin);
+
+        // Drawn on top of the simulation, which is the same without it. glTF scenes need the
+        // renderer, and without them there's nothing to animate.
+        #[cfg(feature = "render")]
+        group
+            .add(visuals::VisualsPlugin)
+            .add(animation::AnimationHelpersPlugin);
+    }
+}
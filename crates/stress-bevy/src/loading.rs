@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{archetypes::MonsterRegistry, assets::AssetManifest};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    Loading,
+    Playing,
+}
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state(AppState::Loading)
+            .add_system_set(SystemSet::on_enter(AppState::Loading).with_system(pause_physics))
+            .add_system_set(SystemSet::on_update(AppState::Loading).with_system(track_loading))
+            .add_system_set(SystemSet::on_exit(AppState::Loading).with_system(resume_physics))
+            .add_system_set(SystemSet::on_enter(AppState::Playing).with_system(start_benchmark));
+    }
+}
+
+fn pause_physics(mut config: ResMut<RapierConfiguration>) {
+    config.physics_pipeline_active = false;
+}
+
+fn resume_physics(mut config: ResMut<RapierConfiguration>) {
+    config.physics_pipeline_active = true;
+}
+
+// Failed entries count as done: `replace_failed_scenes` has already swapped in a placeholder.
+// Monster models only join the manifest once the registry is built, so it waits for that too.
+fn track_loading(
+    manifest: Res<AssetManifest>,
+    registry: Option<Res<MonsterRegistry>>,
+    mut state: ResMut<State<AppState>>,
+    mut last_settled: Local<Option<usize>>,
+) {
+    let total = manifest.entries.len();
+    let settled = manifest.entries.iter().filter(|e| e.is_settled()).count();
+
+    if *last_settled != Some(settled) {
+        *last_settled = Some(settled);
+        info!("loading assets: {}/{}", settled, total);
+    }
+
+    if manifest.is_settled() && registry.is_some() {
+        state.set(AppState::Playing).unwrap();
+    }
+}
+
+// Nothing to store: the clock and the recorder only count frames spent in `AppState::Playing`, so
+// asset loading doesn't skew the first waves.
+fn start_benchmark() {
+    info!("assets ready, starting benchmark");
+}
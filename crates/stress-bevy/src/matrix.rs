@@ -0,0 +1,34 @@
+//! The combined results file of a parameter matrix, one metrics file per run.
+
+use std::{collections::BTreeMap, fs::File, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::MetricsFile;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MatrixResults {
+    pub runs: Vec<MatrixRun>,
+}
+
+/// One repetition of one combination of parameters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixRun {
+    /// Parameter name to value, like `pooling` to `on`.
+    pub parameters: BTreeMap<String, String>,
+    /// Counting from 1.
+    pub repetition: u32,
+    pub metrics: MetricsFile,
+}
+
+impl MatrixResults {
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        serde_json::from_reader(io::BufReader::new(file)).map_err(io::Error::from)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer(io::BufWriter::new(file), self).map_err(io::Error::from)
+    }
+}
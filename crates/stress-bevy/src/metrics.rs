@@ -0,0 +1,281 @@
+//! The metrics file a benchmark run writes, and helpers for reading it back.
+
+use std::{collections::BTreeMap, fs::File, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::steady_state::SteadyState;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsFile {
+    /// Where the run came from. Missing from files written before it was recorded.
+    #[serde(default)]
+    pub environment: Option<Environment>,
+    /// How transient frames were picked out, if they were.
+    #[serde(default)]
+    pub steady_state: Option<SteadyState>,
+    pub frames: Vec<FrameRecord>,
+}
+
+/// The machine, build and scenario a run was measured with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Environment {
+    pub cpu: Option<String>,
+    /// Physical cores.
+    pub cores: Option<usize>,
+    /// Hardware threads available to the process.
+    pub threads: Option<usize>,
+    pub memory_bytes: Option<u64>,
+    /// `linux-x86_64` and the like.
+    pub os: String,
+    pub kernel: Option<String>,
+    pub rustc: String,
+    pub profile: String,
+    pub opt_level: String,
+    /// Commit the binary was built from, suffixed with `-dirty` for uncommitted changes.
+    pub git_commit: String,
+    pub features: Vec<String>,
+    /// Command line of the run.
+    pub args: Vec<String>,
+    /// Everything the run was configured with after defaults were resolved, less the seed and the
+    /// paths it read and wrote.
+    pub scenario: String,
+    /// Seed the simulation ran with, drawn at random when none was given.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl Environment {
+    /// Describes this process. `scenario` is the resolved configuration of the run.
+    pub fn detect(scenario: String, seed: u64) -> Self {
+        let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").ok();
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok();
+
+        Self {
+            cpu: cpuinfo
+                .as_deref()
+                .and_then(|info| proc_field(info, "model name"))
+                .map(str::to_string),
+            cores: cpuinfo.as_deref().and_then(physical_cores),
+            threads: std::thread::available_parallelism()
+                .ok()
+                .map(|threads| threads.get()),
+            memory_bytes: meminfo
+                .as_deref()
+                .and_then(|info| proc_field(info, "MemTotal"))
+                .and_then(parse_kilobytes),
+            os: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+            kernel: std::fs::read_to_string("/proc/sys/kernel/osrelease")
+                .ok()
+                .map(|release| release.trim().to_string()),
+            rustc: env!("STRESS_RUSTC_VERSION").to_string(),
+            profile: env!("STRESS_PROFILE").to_string(),
+            opt_level: env!("STRESS_OPT_LEVEL").to_string(),
+            git_commit: env!("STRESS_GIT_COMMIT").to_string(),
+            features: env!("STRESS_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .map(str::to_string)
+                .collect(),
+            args: std::env::args().collect(),
+            scenario,
+            seed: Some(seed),
+        }
+    }
+
+    /// Everything that makes two runs' timings incomparable, as `(field, ours, theirs)`. The
+    /// commit and command line are left out, since comparing changes is the point.
+    pub fn differences(&self, other: &Self) -> Vec<(&'static str, String, String)> {
+        let fields = [
+            ("cpu", format!("{:?}", self.cpu), format!("{:?}", other.cpu)),
+            (
+                "cores",
+                format!("{:?}", self.cores),
+                format!("{:?}", other.cores),
+            ),
+            (
+                "threads",
+                format!("{:?}", self.threads),
+                format!("{:?}", other.threads),
+            ),
+            (
+                "memory",
+                format!("{:?}", self.memory_bytes),
+                format!("{:?}", other.memory_bytes),
+            ),
+            ("os", self.os.clone(), other.os.clone()),
+            (
+                "kernel",
+                format!("{:?}", self.kernel),
+                format!("{:?}", other.kernel),
+            ),
+            ("rustc", self.rustc.clone(), other.rustc.clone()),
+            ("profile", self.profile.clone(), other.profile.clone()),
+            ("opt-level", self.opt_level.clone(), other.opt_level.clone()),
+            (
+                "features",
+                self.features.join(","),
+                other.features.join(","),
+            ),
+            ("scenario", self.scenario.clone(), other.scenario.clone()),
+        ];
+
+        fields.into_iter().filter(|(_, a, b)| a != b).collect()
+    }
+}
+
+// `name : value` lines, as in /proc/cpuinfo and /proc/meminfo
+fn proc_field<'a>(info: &'a str, name: &str) -> Option<&'a str> {
+    info.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == name).then(|| value.trim())
+    })
+}
+
+fn parse_kilobytes(value: &str) -> Option<u64> {
+    let kilobytes: u64 = value.strip_suffix("kB")?.trim().parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+// Distinct (physical id, core id) pairs, since hyperthreads repeat them
+fn physical_cores(cpuinfo: &str) -> Option<usize> {
+    let mut cores = std::collections::HashSet::new();
+    for processor in cpuinfo.split("\n\n") {
+        let package = proc_field(processor, "physical id").unwrap_or("0");
+        if let Some(core) = proc_field(processor, "core id") {
+            cores.insert((package, core));
+        }
+    }
+    (!cores.is_empty()).then(|| cores.len())
+}
+
+/// One simulation tick.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameRecord {
+    pub tick: u64,
+    /// Simulated seconds since gameplay started.
+    pub elapsed: f64,
+    /// Wall-clock time of the frame.
+    pub frame_time_ms: f64,
+    /// Waves spawned so far.
+    #[serde(default)]
+    pub wave: u32,
+    pub monsters: u32,
+    pub projectiles: u32,
+    /// Hash of every monster, projectile and character transform and velocity.
+    pub world_hash: u64,
+    /// Resident set size of the process, where the platform reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_bytes: Option<u64>,
+    /// Milliseconds spent in each system, and in each stage as `stage <label>`, when the run
+    /// was profiled. Stages contain their systems, so don't sum the two together.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub systems: BTreeMap<String, f64>,
+    /// Warmup or the spike after a wave, left out of summaries. See `SteadyState`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub transient: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl MetricsFile {
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        serde_json::from_reader(io::BufReader::new(file)).map_err(io::Error::from)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer(io::BufWriter::new(file), self).map_err(io::Error::from)
+    }
+
+    /// The frames summaries should be computed from: steady-state ones only, unless
+    /// `include_transient` is set. A run that never settled falls back to every frame, with a
+    /// warning, rather than summarizing nothing.
+    pub fn summary_frames(&self, include_transient: bool) -> impl Iterator<Item = &FrameRecord> {
+        let settled = self.frames.iter().any(|frame| !frame.transient);
+        if !include_transient && !settled && !self.frames.is_empty() {
+            eprintln!(
+                "warning: none of the {} frames settled, summarizing all of them",
+                self.frames.len()
+            );
+        }
+
+        let include_transient = include_transient || !settled;
+        self.frames
+            .iter()
+            .filter(move |frame| include_transient || !frame.transient)
+    }
+}
+
+/// Resident memory of this process. Only implemented on Linux, from `/proc/self/status`.
+pub fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    proc_field(&status, "VmRSS").and_then(parse_kilobytes)
+}
+
+/// The first tick at which two runs stopped simulating the same world.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    Hash {
+        tick: u64,
+        a: u64,
+        b: u64,
+    },
+    /// One run recorded fewer ticks than the other.
+    Length {
+        a: usize,
+        b: usize,
+    },
+}
+
+pub fn first_divergence(a: &[FrameRecord], b: &[FrameRecord]) -> Option<Divergence> {
+    for (a, b) in a.iter().zip(b) {
+        if a.world_hash != b.world_hash {
+            return Some(Divergence::Hash {
+                tick: a.tick,
+                a: a.world_hash,
+                b: b.world_hash,
+            });
+        }
+    }
+
+    if a.len() != b.len() {
+        return Some(Divergence::Length {
+            a: a.len(),
+            b: b.len(),
+        });
+    }
+    None
+}
+
+/// FNV-1a, so hashes are stable across runs, platforms and Rust versions.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl StableHasher {
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn write_f32s(&mut self, values: &[f32]) {
+        for value in values {
+            self.write(&value.to_bits().to_le_bytes());
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
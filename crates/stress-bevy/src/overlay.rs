@@ -0,0 +1,219 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    diagnostic::{DiagnosticId, Diagnostics, FrameTimeDiagnosticsPlugin},
+    input::{keyboard::KeyboardInput, ElementState},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+
+use crate::{assets, combat::Projectile, waves::Monster};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+const SPARKLINE_SAMPLES: usize = 120;
+const SPARKLINE_HEIGHT: f32 = 48.;
+/// The graph never scales below this, so a smooth 60 FPS doesn't look like a wall of spikes.
+const SPARKLINE_MIN_SCALE_MS: f32 = 33.3;
+
+/// Live stats panel in the top-left corner, toggled with F3.
+pub struct OverlayPlugin;
+
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(FrameTimeDiagnosticsPlugin)
+            .init_resource::<FrameTimeHistory>()
+            .add_startup_system(spawn_overlay)
+            .add_system(toggle_overlay)
+            .add_system(record_frame_time)
+            .add_system(
+                update_overlay
+                    .after(record_frame_time)
+                    .after(toggle_overlay),
+            );
+    }
+}
+
+#[derive(Default)]
+struct FrameTimeHistory(VecDeque<f32>);
+
+#[derive(Component)]
+struct Overlay;
+
+#[derive(Component)]
+struct OverlayText;
+
+/// One bar of the frame-time graph, oldest first.
+#[derive(Component)]
+struct SparklineBar(usize);
+
+fn spawn_overlay(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let style = TextStyle {
+        font: asset_server.load(assets::OVERLAY_FONT),
+        font_size: 16.,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(10.),
+                    left: Val::Px(10.),
+                    ..default()
+                },
+                padding: Rect::all(Val::Px(8.)),
+                // UI is laid out bottom-up, so reverse the column to read top to bottom
+                flex_direction: FlexDirection::ColumnReverse,
+                ..default()
+            },
+            color: Color::rgba(0., 0., 0., 0.6).into(),
+            ..default()
+        })
+        .insert(Overlay)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section("", style, default()),
+                    ..default()
+                })
+                .insert(OverlayText);
+
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Auto, Val::Px(SPARKLINE_HEIGHT)),
+                        margin: Rect {
+                            top: Val::Px(6.),
+                            ..default()
+                        },
+                        align_items: AlignItems::FlexStart,
+                        ..default()
+                    },
+                    color: Color::rgba(1., 1., 1., 0.1).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for i in 0..SPARKLINE_SAMPLES {
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(2.), Val::Percent(0.)),
+                                    ..default()
+                                },
+                                color: Color::GREEN.into(),
+                                ..default()
+                            })
+                            .insert(SparklineBar(i));
+                    }
+                });
+        });
+}
+
+fn toggle_overlay(
+    mut keys: EventReader<KeyboardInput>,
+    mut overlay: Query<&mut Style, With<Overlay>>,
+) {
+    // Raw key events, so the toggle still works while a replay or the autopilot owns the input
+    let toggled = keys
+        .iter()
+        .filter(|key| key.state == ElementState::Pressed && key.key_code == Some(TOGGLE_KEY))
+        .count()
+        % 2
+        == 1;
+    if !toggled {
+        return;
+    }
+
+    for mut style in overlay.iter_mut() {
+        style.display = match style.display {
+            Display::None => Display::Flex,
+            Display::Flex => Display::None,
+        };
+    }
+}
+
+fn record_frame_time(mut history: ResMut<FrameTimeHistory>, time: Res<Time>) {
+    if history.0.len() == SPARKLINE_SAMPLES {
+        history.0.pop_front();
+    }
+    history.0.push_back(time.delta_seconds() * 1000.);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_overlay(
+    overlay: Query<&Style, With<Overlay>>,
+    mut text: Query<&mut Text, With<OverlayText>>,
+    mut bars: Query<(&SparklineBar, &mut Style, &mut UiColor), Without<Overlay>>,
+    history: Res<FrameTimeHistory>,
+    diagnostics: Res<Diagnostics>,
+    rapier_context: Res<RapierContext>,
+    monsters: Query<(), With<Monster>>,
+    projectiles: Query<(), With<Projectile>>,
+    bodies: Query<(), With<RigidBody>>,
+    animation_players: Query<(), With<AnimationPlayer>>,
+) {
+    match overlay.get_single() {
+        Ok(style) if style.display == Display::Flex => {}
+        _ => return,
+    }
+
+    let average = |id: DiagnosticId| {
+        diagnostics
+            .get(id)
+            .and_then(|diagnostic| diagnostic.average())
+            .unwrap_or(0.)
+    };
+    let fps = average(FrameTimeDiagnosticsPlugin::FPS);
+    let frame_time_ms = average(FrameTimeDiagnosticsPlugin::FRAME_TIME) * 1000.;
+    let contacts = rapier_context
+        .narrow_phase
+        .contact_pairs()
+        .filter(|pair| pair.has_any_active_contact)
+        .count();
+
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = format!(
+            "FPS         {:>7.1}\n\
+             frame time  {:>7.2} ms\n\
+             monsters    {:>7}\n\
+             projectiles {:>7}\n\
+             bodies      {:>7}\n\
+             contacts    {:>7}\n\
+             animators   {:>7}",
+            fps,
+            frame_time_ms,
+            monsters.iter().count(),
+            projectiles.iter().count(),
+            bodies.iter().count(),
+            contacts,
+            animation_players.iter().count(),
+        );
+    }
+
+    let scale = history
+        .0
+        .iter()
+        .copied()
+        .fold(SPARKLINE_MIN_SCALE_MS, f32::max);
+    // Right-align the samples so the newest frame is always the last bar
+    let offset = SPARKLINE_SAMPLES - history.0.len();
+    for (bar, mut style, mut color) in bars.iter_mut() {
+        let ms = bar
+            .0
+            .checked_sub(offset)
+            .and_then(|i| history.0.get(i))
+            .copied()
+            .unwrap_or(0.);
+
+        style.size.height = Val::Percent(ms / scale * 100.);
+        color.0 = if ms > SPARKLINE_MIN_SCALE_MS {
+            Color::RED
+        } else if ms > 16.7 {
+            Color::YELLOW
+        } else {
+            Color::GREEN
+        };
+    }
+}
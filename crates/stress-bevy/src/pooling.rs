@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{combat::Projectile, scenario::Scenario, weapons::Explosion};
+
+/// Far below the arena, where parked projectiles wait out of sight.
+const PARKING_SPOT: Vec3 = Vec3::new(0., -1000., 0.);
+
+/// Projectiles that hit something or missed, parked out of the world so later shots can reuse their
+/// entities and rigid bodies. Without `--pooling` they're despawned as before.
+#[derive(Default)]
+pub struct ProjectilePool {
+    enabled: bool,
+    free: Vec<Entity>,
+    /// Parked this frame. Kept apart from `free` until the parking commands have been applied,
+    /// and so a projectile hitting two things at once is only parked once. Kept in the order
+    /// they hit, so reuse is the same from run to run.
+    retiring: Vec<Entity>,
+}
+
+impl ProjectilePool {
+    /// Takes a projectile out of the world, parking it when pooling and despawning it otherwise.
+    pub fn retire(&mut self, commands: &mut Commands, projectile: Entity) {
+        if !self.enabled {
+            commands.entity(projectile).despawn();
+            return;
+        }
+        if self.retiring.contains(&projectile) {
+            return;
+        }
+        self.retiring.push(projectile);
+
+        // Colliding with nothing and without the markers, it drops out of the simulation, the
+        // metrics and the world hash
+        commands
+            .entity(projectile)
+            .remove::<Projectile>()
+            .remove::<Explosion>()
+            .insert(CollisionGroups::new(0, 0))
+            .insert(GravityScale(0.))
+            .insert(Velocity::default())
+            .insert(Transform::from_translation(PARKING_SPOT));
+    }
+
+    /// A parked projectile to fire again, if there is one. Every component it needs is
+    /// inserted again by the caller.
+    pub fn take(&mut self) -> Option<Entity> {
+        self.free.pop()
+    }
+}
+
+pub struct PoolingPlugin;
+
+impl Plugin for PoolingPlugin {
+    fn build(&self, app: &mut App) {
+        let enabled = app.world.resource::<Scenario>().pooling;
+        app.insert_resource(ProjectilePool {
+            enabled,
+            ..default()
+        })
+        .add_system_to_stage(CoreStage::First, free_retired);
+    }
+}
+
+// Last frame's parking has been applied by now
+fn free_retired(mut pool: ResMut<ProjectilePool>) {
+    let pool = &mut *pool;
+    pool.free.extend(pool.retiring.drain(..));
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    app::AppExit,
+    prelude::*,
+    utils::tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id},
+        Subscriber,
+    },
+};
+use tracing_chrome::{ChromeLayerBuilder, EventOrSpan, FlushGuard};
+use tracing_log::LogTracer;
+use tracing_subscriber::{
+    layer::{Context, Layer},
+    prelude::*,
+    registry::{LookupSpan, Registry},
+    EnvFilter,
+};
+
+use crate::scenario::Scenario;
+
+/// Time spent in each system and stage since the last recorded frame. Filled in by the tracing
+/// layer from Bevy's `system` and `stage` spans, drained by the metrics recorder every frame.
+#[derive(Clone, Default)]
+pub struct SystemTimings(Arc<Mutex<HashMap<String, Duration>>>);
+
+impl SystemTimings {
+    /// Milliseconds per system, resetting the totals for the next frame.
+    pub fn drain(&self) -> BTreeMap<String, f64> {
+        let mut totals = self.0.lock().unwrap();
+        totals
+            .drain()
+            .map(|(name, time)| (name, time.as_secs_f64() * 1000.))
+            .collect()
+    }
+}
+
+/// Kept until the app exits so the Chrome trace gets flushed; the winit runner never returns.
+struct ChromeTrace(Mutex<Option<FlushGuard>>);
+
+/// The subscriber state the app holds on to. Bevy's `LogPlugin` must be left out of
+/// `DefaultPlugins` when this exists, since logging goes through the same subscriber.
+pub struct Profiling {
+    timings: SystemTimings,
+    chrome: Option<FlushGuard>,
+}
+
+impl Profiling {
+    pub fn add_to(self, app: &mut App) {
+        app.insert_resource(self.timings)
+            .insert_resource(ChromeTrace(Mutex::new(self.chrome)))
+            .add_system_to_stage(CoreStage::Last, flush_chrome_trace);
+    }
+}
+
+/// Installs the tracing subscriber when the scenario asks for system timings or a Chrome trace.
+/// Must run before the app is built, since the subscriber is global.
+pub fn init(scenario: &Scenario) -> Option<Profiling> {
+    if !scenario.profile_systems && scenario.chrome_trace.is_none() {
+        return None;
+    }
+
+    // What `LogPlugin` would have set up, plus the timing layer
+    LogTracer::init().unwrap();
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new("info,wgpu=error"))
+        .unwrap();
+    let timings = SystemTimings::default();
+    // Only on the log output: `RUST_LOG=warn` shouldn't take the spans away from the timings
+    // and the trace
+    let subscriber = Registry::default()
+        .with(tracing_subscriber::fmt::Layer::default().with_filter(filter))
+        .with(TimingLayer(timings.clone()));
+
+    let chrome = match &scenario.chrome_trace {
+        Some(path) => {
+            let (layer, guard) = ChromeLayerBuilder::new()
+                .file(path)
+                // Show `spawn_waves` rather than a wall of identical `system` spans
+                .name_fn(Box::new(|event_or_span| match event_or_span {
+                    EventOrSpan::Event(event) => event.metadata().name().into(),
+                    EventOrSpan::Span(span) => match span.extensions().get::<TimedSpan>() {
+                        Some(timed) => timed.name.clone(),
+                        None => span.name().into(),
+                    },
+                }))
+                .build();
+            bevy::utils::tracing::subscriber::set_global_default(subscriber.with(layer))
+                .expect("failed to set the tracing subscriber");
+            Some(guard)
+        }
+        None => {
+            bevy::utils::tracing::subscriber::set_global_default(subscriber)
+                .expect("failed to set the tracing subscriber");
+            None
+        }
+    };
+
+    // Bevy's `trace` feature comes with the renderer
+    if scenario.profile_systems && !cfg!(feature = "render") {
+        warn!("built without the `render` feature, so there are no system spans to time");
+    }
+
+    Some(Profiling { timings, chrome })
+}
+
+fn flush_chrome_trace(trace: Res<ChromeTrace>, mut exits: EventReader<AppExit>) {
+    if exits.iter().next().is_some() {
+        // Dropping the guard writes out the rest of the trace
+        trace.0.lock().unwrap().take();
+    }
+}
+
+struct TimingLayer(SystemTimings);
+
+struct TimedSpan {
+    name: String,
+    entered: Option<Instant>,
+}
+
+impl<S> Layer<S> for TimingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let prefix = match attrs.metadata().name() {
+            "system" => "",
+            "stage" => "stage ",
+            _ => return,
+        };
+
+        let mut visitor = NameVisitor(None);
+        attrs.record(&mut visitor);
+        if let (Some(name), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(TimedSpan {
+                name: format!("{}{}", prefix, name),
+                entered: None,
+            });
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(timed) = span.extensions_mut().get_mut::<TimedSpan>() {
+                timed.entered = Some(Instant::now());
+            }
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(timed) = span.extensions_mut().get_mut::<TimedSpan>() {
+                if let Some(entered) = timed.entered.take() {
+                    let mut totals = (self.0).0.lock().unwrap();
+                    *totals.entry(timed.name.clone()).or_default() += entered.elapsed();
+                }
+            }
+        }
+    }
+}
+
+// Systems name their span with a string, stages with the label's `Debug` output
+struct NameVisitor(Option<String>);
+
+impl Visit for NameVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "name" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "name" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::{app::AppExit, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    character::Character,
+    combat::Projectile,
+    loading::AppState,
+    metrics::{resident_memory, Environment, FrameRecord, MetricsFile, StableHasher},
+    profiling::SystemTimings,
+    scenario::Scenario,
+    simulation::{SimulationClock, SimulationRng},
+    waves::{Monster, Wave},
+};
+
+/// Per-tick metrics, finished when the app exits and written to `Scenario::metrics` if given.
+#[derive(Default)]
+pub struct MetricsRecorder {
+    pub file: MetricsFile,
+}
+
+pub struct RecorderPlugin;
+
+impl Plugin for RecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MetricsRecorder>()
+            .add_system_to_stage(CoreStage::Last, record_frame)
+            .add_system_to_stage(CoreStage::Last, exit_after_ticks.after(record_frame))
+            .add_system_to_stage(CoreStage::Last, write_metrics.after(exit_after_ticks));
+    }
+}
+
+/// Order-independent hash of the simulated world, so entity ids and query order don't matter.
+pub fn world_hash<'a>(
+    bodies: impl Iterator<Item = (&'a Transform, Option<&'a Velocity>, u8)>,
+) -> u64 {
+    let mut combined = 0u64;
+    let mut count = 0u64;
+
+    for (transform, velocity, kind) in bodies {
+        let mut hasher = StableHasher::default();
+        hasher.write(&[kind]);
+        hasher.write_f32s(&transform.translation.to_array());
+        hasher.write_f32s(&transform.rotation.to_array());
+        if let Some(velocity) = velocity {
+            hasher.write_f32s(&velocity.linvel.to_array());
+            hasher.write_f32s(&velocity.angvel.to_array());
+        }
+
+        combined = combined.wrapping_add(hasher.finish());
+        count += 1;
+    }
+
+    let mut hasher = StableHasher::default();
+    hasher.write(&combined.to_le_bytes());
+    hasher.write(&count.to_le_bytes());
+    hasher.finish()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_frame(
+    mut recorder: ResMut<MetricsRecorder>,
+    state: Res<State<AppState>>,
+    clock: Res<SimulationClock>,
+    time: Res<Time>,
+    wave: Res<Wave>,
+    monsters: Query<(&Transform, Option<&Velocity>), With<Monster>>,
+    projectiles: Query<(&Transform, Option<&Velocity>), With<Projectile>>,
+    characters: Query<(&Transform, Option<&Velocity>), With<Character>>,
+    timings: Option<Res<SystemTimings>>,
+) {
+    // Drained every frame, so loading doesn't pile up in the first recorded tick
+    let systems = timings.map(|timings| timings.drain()).unwrap_or_default();
+
+    // Tick 0 is the frame gameplay starts on, before anything has been simulated
+    if *state.current() != AppState::Playing || clock.tick == 0 {
+        return;
+    }
+
+    let bodies = monsters
+        .iter()
+        .map(|(t, v)| (t, v, 0))
+        .chain(projectiles.iter().map(|(t, v)| (t, v, 1)))
+        .chain(characters.iter().map(|(t, v)| (t, v, 2)));
+
+    let record = FrameRecord {
+        tick: clock.tick,
+        elapsed: clock.elapsed,
+        frame_time_ms: time.delta_seconds_f64() * 1000.,
+        wave: wave.0,
+        monsters: monsters.iter().count() as u32,
+        projectiles: projectiles.iter().count() as u32,
+        world_hash: world_hash(bodies),
+        memory_bytes: resident_memory(),
+        systems,
+    };
+    recorder.file.frames.push(record);
+}
+
+fn exit_after_ticks(
+    scenario: Res<Scenario>,
+    clock: Res<SimulationClock>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if let Some(ticks) = scenario.ticks {
+        if clock.tick >= ticks {
+            exit.send(AppExit);
+        }
+    }
+}
+
+fn write_metrics(
+    scenario: Res<Scenario>,
+    sim_rng: Res<SimulationRng>,
+    mut recorder: ResMut<MetricsRecorder>,
+    mut exits: EventReader<AppExit>,
+) {
+    if exits.iter().next().is_none() {
+        return;
+    }
+
+    // Paths and the seed don't change what was measured, and would make every run look different
+    let resolved = Scenario {
+        seed: None,
+        metrics: None,
+        record: None,
+        replay: None,
+        chrome_trace: None,
+        ..scenario.clone()
+    };
+    recorder.file.environment = Some(Environment::detect(format!("{:?}", resolved), sim_rng.seed));
+    recorder.file.steady_state = Some(scenario.steady_state);
+    scenario
+        .steady_state
+        .mark_transients(&mut recorder.file.frames);
+
+    // Runs in this process read the recorder instead, see `headless::run_headless`
+    if let Some(path) = &scenario.metrics {
+        match recorder.file.write(path) {
+            Ok(()) => info!(
+                "wrote {} frames of metrics to {}",
+                recorder.file.frames.len(),
+                path.display()
+            ),
+            Err(err) => error!("failed to write metrics to {}: {}", path.display(), err),
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::{fs::File, io, path::Path};
+
+use bevy::{
+    app::AppExit,
+    input::{mouse::MouseMotion, InputSystem},
+    prelude::*,
+    utils::HashSet,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    loading::AppState,
+    scenario::{FixedTimestep, Scenario},
+    simulation::{advance_clock, SimulationClock, SimulationRng},
+};
+
+/// The inputs gameplay consumed during a run, keyed by simulation tick. Replaying needs the same
+/// scenario flags as the recording; the seed and timestep are restored from the file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayFile {
+    pub seed: u64,
+    pub hz: f32,
+    pub substeps: usize,
+    pub ticks: u64,
+    /// Only ticks where something changed.
+    pub frames: Vec<InputFrame>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputFrame {
+    pub tick: u64,
+    /// Every key held from this tick on, when that changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Vec<KeyCode>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buttons: Option<Vec<MouseButton>>,
+    /// Summed mouse motion over the tick.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mouse_motion: Option<Vec2>,
+}
+
+impl InputFrame {
+    fn new(tick: u64) -> Self {
+        Self {
+            tick,
+            keys: None,
+            buttons: None,
+            mouse_motion: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.keys.is_none() && self.buttons.is_none() && self.mouse_motion.is_none()
+    }
+}
+
+impl ReplayFile {
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        serde_json::from_reader(io::BufReader::new(file)).map_err(io::Error::from)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer(io::BufWriter::new(file), self).map_err(io::Error::from)
+    }
+
+    /// Runs the scenario in lockstep with the recording. `--ticks` can still cut it short.
+    pub fn apply_to(&self, scenario: &mut Scenario) {
+        scenario.seed = Some(self.seed);
+        scenario.fixed_timestep = Some(FixedTimestep {
+            hz: self.hz,
+            substeps: self.substeps,
+        });
+        scenario.ticks.get_or_insert(self.ticks);
+    }
+}
+
+#[derive(Default)]
+struct Recording {
+    file: ReplayFile,
+    keys: HashSet<KeyCode>,
+    buttons: HashSet<MouseButton>,
+}
+
+/// Input state rebuilt from the replay, copied over the real input every frame.
+struct Playback {
+    frames: std::vec::IntoIter<InputFrame>,
+    next: Option<InputFrame>,
+    keys: Input<KeyCode>,
+    buttons: Input<MouseButton>,
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let scenario = app.world.resource::<Scenario>();
+        let (replay, record) = (scenario.replay.clone(), scenario.record.is_some());
+
+        if let Some(path) = replay {
+            let replay = ReplayFile::read(&path).unwrap_or_else(|err| {
+                eprintln!("failed to read replay {}: {}", path.display(), err);
+                std::process::exit(2);
+            });
+            info!(
+                "replaying {} ticks of input from {}",
+                replay.ticks,
+                path.display()
+            );
+            replay.apply_to(&mut app.world.resource_mut::<Scenario>());
+
+            let mut frames = replay.frames.into_iter();
+            app.insert_resource(Playback {
+                next: frames.next(),
+                frames,
+                keys: default(),
+                buttons: default(),
+            })
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                play_inputs.after(InputSystem).after(advance_clock),
+            );
+        } else if record {
+            app.init_resource::<Recording>()
+                .add_system_to_stage(
+                    CoreStage::PreUpdate,
+                    record_inputs.after(InputSystem).after(advance_clock),
+                )
+                .add_system_to_stage(CoreStage::Last, write_recording);
+        }
+    }
+}
+
+fn record_inputs(
+    mut recording: ResMut<Recording>,
+    state: Res<State<AppState>>,
+    clock: Res<SimulationClock>,
+    keyboard: Res<Input<KeyCode>>,
+    mouse_button: Res<Input<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+) {
+    let motion = mouse_motion
+        .iter()
+        .fold(Vec2::ZERO, |motion, event| motion + event.delta);
+    if *state.current() != AppState::Playing {
+        return;
+    }
+
+    let mut frame = InputFrame::new(clock.tick);
+
+    let keys: HashSet<KeyCode> = keyboard.get_pressed().copied().collect();
+    if keys != recording.keys {
+        let mut sorted: Vec<KeyCode> = keys.iter().copied().collect();
+        sorted.sort();
+        frame.keys = Some(sorted);
+        recording.keys = keys;
+    }
+
+    let buttons: HashSet<MouseButton> = mouse_button.get_pressed().copied().collect();
+    if buttons != recording.buttons {
+        frame.buttons = Some(buttons.iter().copied().collect());
+        recording.buttons = buttons;
+    }
+
+    if motion != Vec2::ZERO {
+        frame.mouse_motion = Some(motion);
+    }
+
+    recording.file.ticks = clock.tick;
+    if !frame.is_empty() {
+        recording.file.frames.push(frame);
+    }
+}
+
+fn play_inputs(
+    mut playback: ResMut<Playback>,
+    state: Res<State<AppState>>,
+    clock: Res<SimulationClock>,
+    mut keyboard: ResMut<Input<KeyCode>>,
+    mut mouse_button: ResMut<Input<MouseButton>>,
+    mut mouse_motion: ResMut<Events<MouseMotion>>,
+) {
+    // Nothing from the real devices gets through, even while loading
+    mouse_motion.clear();
+    playback.keys.clear();
+    playback.buttons.clear();
+
+    if *state.current() == AppState::Playing {
+        let playback = &mut *playback;
+        while let Some(frame) = playback.next.take() {
+            if frame.tick > clock.tick {
+                playback.next = Some(frame);
+                break;
+            }
+
+            if let Some(keys) = frame.keys {
+                apply_pressed(&mut playback.keys, &keys);
+            }
+            if let Some(buttons) = frame.buttons {
+                apply_pressed(&mut playback.buttons, &buttons);
+            }
+            if let Some(delta) = frame.mouse_motion {
+                mouse_motion.send(MouseMotion { delta });
+            }
+            playback.next = playback.frames.next();
+        }
+    }
+
+    *keyboard = playback.keys.clone();
+    *mouse_button = playback.buttons.clone();
+}
+
+// Presses and releases whatever differs from the recorded set of held inputs
+fn apply_pressed<T>(input: &mut Input<T>, pressed: &[T])
+where
+    T: Copy + Eq + std::hash::Hash,
+{
+    let released: Vec<T> = input
+        .get_pressed()
+        .filter(|held| !pressed.contains(held))
+        .copied()
+        .collect();
+    for held in released {
+        input.release(held);
+    }
+    for &held in pressed {
+        input.press(held);
+    }
+}
+
+fn write_recording(
+    scenario: Res<Scenario>,
+    recording: Res<Recording>,
+    sim_rng: Res<SimulationRng>,
+    mut exits: EventReader<AppExit>,
+) {
+    if exits.iter().next().is_none() {
+        return;
+    }
+
+    let path = match &scenario.record {
+        Some(path) => path,
+        None => return,
+    };
+    // Recording turns on the fixed timestep, see `Scenario::parse`
+    let fixed = scenario.fixed_timestep.unwrap_or_default();
+    let file = ReplayFile {
+        seed: sim_rng.seed,
+        hz: fixed.hz,
+        substeps: fixed.substeps,
+        ..recording.file.clone()
+    };
+
+    match file.write(path) {
+        Ok(()) => info!(
+            "recorded {} ticks of input to {}",
+            file.ticks,
+            path.display()
+        ),
+        Err(err) => error!("failed to write replay to {}: {}", path.display(), err),
+    }
+}
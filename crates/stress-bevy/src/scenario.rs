@@ -0,0 +1,307 @@
+use std::{path::PathBuf, str::FromStr};
+
+use crate::{
+    collision_layers::{CollisionMatrix, Layer},
+    steady_state::SteadyState,
+};
+
+/// How monsters and the character are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelMode {
+    /// Animated glTF scenes.
+    Gltf,
+    /// Built-in meshes only, with no scene spawning or animation setup.
+    Primitives(PrimitiveShape),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrimitiveShape {
+    Capsule,
+    Cube,
+}
+
+impl FromStr for PrimitiveShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "capsule" => Ok(PrimitiveShape::Capsule),
+            "cube" => Ok(PrimitiveShape::Cube),
+            _ => Err(format!("unknown primitive shape '{}'", s)),
+        }
+    }
+}
+
+/// Lockstep physics and gameplay: every frame is one tick of exactly `1 / hz` seconds, however
+/// long it took. Not a fixed schedule with an accumulator, so gameplay systems still run once
+/// per frame and a slow machine plays the same ticks in slow motion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedTimestep {
+    pub hz: f32,
+    pub substeps: usize,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self {
+            hz: 60.,
+            substeps: 1,
+        }
+    }
+}
+
+/// How the autopilot moves the character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrafePattern {
+    /// Strafe one way, circling whatever the camera looks at.
+    Circle,
+    /// Strafe left and right.
+    Sweep,
+    /// Advance while weaving left and right.
+    Zigzag,
+}
+
+impl FromStr for StrafePattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "circle" => Ok(StrafePattern::Circle),
+            "sweep" => Ok(StrafePattern::Sweep),
+            "zigzag" => Ok(StrafePattern::Zigzag),
+            _ => Err(format!("unknown strafe pattern '{}'", s)),
+        }
+    }
+}
+
+/// A bot that plays in place of the keyboard and mouse.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Autopilot {
+    pub pattern: StrafePattern,
+    /// Trigger pulls per second. The weapon's own fire rate still caps it.
+    pub fire_rate: f32,
+    /// How fast the camera turns toward the nearest monster, in radians per second.
+    pub turn_speed: f32,
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self {
+            pattern: StrafePattern::Sweep,
+            fire_rate: 4.,
+            turn_speed: 3.,
+        }
+    }
+}
+
+/// Options for a stress test run, read from the command line.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    pub models: ModelMode,
+    /// Play the idle animations on glTF models. They're still loaded and spawned when off.
+    pub animation: bool,
+    pub monsters_per_wave: u32,
+    /// Stop spawning after this many waves.
+    pub waves: Option<u32>,
+    /// Side length of the square ground, which waves spawn across.
+    pub arena_size: f32,
+    /// Park projectiles that hit something and fire them again, instead of despawning them.
+    pub pooling: bool,
+    pub collision: CollisionMatrix,
+    /// Continuous collision detection on projectiles, so fast shots can't tunnel through monsters.
+    pub ccd: bool,
+    /// `--fixed-timestep[=hz]`, lockstep ticks instead of wall-clock time. See [`FixedTimestep`].
+    pub fixed_timestep: Option<FixedTimestep>,
+    /// Seed for gameplay randomness, picked at random when unset.
+    pub seed: Option<u64>,
+    /// Exit after this many simulation ticks.
+    pub ticks: Option<u64>,
+    /// Where to write the metrics file on exit.
+    pub metrics: Option<PathBuf>,
+    /// Where to write the recorded input on exit.
+    pub record: Option<PathBuf>,
+    /// Input to play back instead of reading the keyboard and mouse.
+    pub replay: Option<PathBuf>,
+    pub autopilot: Option<Autopilot>,
+    /// Time every system and stage, per frame, into the metrics file.
+    pub profile_systems: bool,
+    /// Where to write a Chrome trace (chrome://tracing or Perfetto) of every span. Implies
+    /// `profile_systems`.
+    pub chrome_trace: Option<PathBuf>,
+    /// How warmup and post-wave frames are told apart from steady-state ones in the metrics.
+    pub steady_state: SteadyState,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            models: ModelMode::Gltf,
+            animation: true,
+            monsters_per_wave: 10,
+            waves: None,
+            arena_size: 500.,
+            pooling: false,
+            collision: CollisionMatrix::default(),
+            ccd: false,
+            fixed_timestep: None,
+            seed: None,
+            ticks: None,
+            metrics: None,
+            record: None,
+            replay: None,
+            autopilot: None,
+            profile_systems: false,
+            chrome_trace: None,
+            steady_state: SteadyState::default(),
+        }
+    }
+}
+
+impl Scenario {
+    pub fn from_args() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(scenario) => scenario,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// Parses `--flag` and `--option=value` style arguments.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut scenario = Self::default();
+
+        for arg in args {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (arg.as_str(), None),
+            };
+
+            match name {
+                "--primitives" => {
+                    let shape = value.map_or(Ok(PrimitiveShape::Capsule), str::parse)?;
+                    scenario.models = ModelMode::Primitives(shape);
+                }
+                "--no-animation" => scenario.animation = false,
+                "--monsters-per-wave" => scenario.monsters_per_wave = parse_value(name, value)?,
+                "--waves" => scenario.waves = Some(parse_value(name, value)?),
+                "--arena-size" => scenario.arena_size = parse_value(name, value)?,
+                "--pooling" => scenario.pooling = true,
+                "--ccd" => scenario.ccd = true,
+                "--fixed-timestep" => {
+                    let fixed = scenario
+                        .fixed_timestep
+                        .get_or_insert_with(FixedTimestep::default);
+                    if value.is_some() {
+                        fixed.hz = parse_value(name, value)?;
+                    }
+                }
+                "--substeps" => {
+                    let fixed = scenario
+                        .fixed_timestep
+                        .get_or_insert_with(FixedTimestep::default);
+                    fixed.substeps = parse_value(name, value)?;
+                }
+                "--seed" => scenario.seed = Some(parse_value(name, value)?),
+                "--ticks" => scenario.ticks = Some(parse_value(name, value)?),
+                "--metrics" => scenario.metrics = Some(parse_value(name, value)?),
+                "--record" => scenario.record = Some(parse_value(name, value)?),
+                "--replay" => scenario.replay = Some(parse_value(name, value)?),
+                "--autopilot" => {
+                    let autopilot = scenario.autopilot.get_or_insert_with(Autopilot::default);
+                    if value.is_some() {
+                        autopilot.pattern = parse_value(name, value)?;
+                    }
+                }
+                "--fire-rate" => {
+                    let autopilot = scenario.autopilot.get_or_insert_with(Autopilot::default);
+                    autopilot.fire_rate = parse_value(name, value)?;
+                }
+                "--turn-speed" => {
+                    let autopilot = scenario.autopilot.get_or_insert_with(Autopilot::default);
+                    autopilot.turn_speed = parse_value(name, value)?;
+                }
+                "--profile-systems" => scenario.profile_systems = true,
+                "--chrome-trace" => {
+                    scenario.chrome_trace = Some(parse_value(name, value)?);
+                    scenario.profile_systems = true;
+                }
+                "--steady-window" => scenario.steady_state.window = parse_value(name, value)?,
+                "--steady-variation" => {
+                    scenario.steady_state.max_variation = parse_value(name, value)?;
+                }
+                "--collide" | "--ignore" => {
+                    let (a, b) = parse_layer_pair(name, value)?;
+                    scenario.collision.set_collision(a, b, name == "--collide");
+                }
+                "--solve" | "--no-solve" => {
+                    let (a, b) = parse_layer_pair(name, value)?;
+                    scenario.collision.set_solver(a, b, name == "--solve");
+                }
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+
+        if scenario.record.is_some() && scenario.replay.is_some() {
+            return Err("--record and --replay can't be used together".to_string());
+        }
+        if scenario.autopilot.is_some() && scenario.replay.is_some() {
+            return Err("--autopilot and --replay can't be used together".to_string());
+        }
+        // The bot turns the camera itself, which the input recording doesn't capture
+        if scenario.autopilot.is_some() && scenario.record.is_some() {
+            return Err("--autopilot and --record can't be used together".to_string());
+        }
+        if scenario.arena_size <= 0. {
+            return Err("--arena-size must be positive".to_string());
+        }
+        if scenario.steady_state.window == 0 {
+            return Err("--steady-window must be at least 1".to_string());
+        }
+        let max_variation = scenario.steady_state.max_variation;
+        if max_variation.is_nan() || max_variation <= 0. {
+            return Err("--steady-variation must be positive".to_string());
+        }
+        if let Some(fixed) = scenario.fixed_timestep {
+            if !fixed.hz.is_finite() || fixed.hz <= 0. {
+                return Err("--fixed-timestep must be a positive rate".to_string());
+            }
+            if fixed.substeps == 0 {
+                return Err("--substeps must be at least 1".to_string());
+            }
+        }
+        // Inputs are keyed by tick, which only means the same thing in lockstep
+        if scenario.record.is_some() {
+            scenario
+                .fixed_timestep
+                .get_or_insert_with(FixedTimestep::default);
+        }
+        // So recorded runs say what was drawn, not what was asked for
+        if !cfg!(feature = "render") && scenario.models == ModelMode::Gltf {
+            scenario.models = ModelMode::Primitives(PrimitiveShape::Capsule);
+        }
+
+        Ok(scenario)
+    }
+
+    /// glTF scenes need the renderer, so without the `render` feature `parse` swaps them for
+    /// capsules and nothing is loaded.
+    pub fn uses_gltf(&self) -> bool {
+        cfg!(feature = "render") && self.models == ModelMode::Gltf
+    }
+}
+
+fn parse_value<T: FromStr>(name: &str, value: Option<&str>) -> Result<T, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("{} expects a value, like {}=<value>", name, name))
+}
+
+// `layer:layer`, e.g. `--collide=projectile:ground`
+fn parse_layer_pair(name: &str, value: Option<&str>) -> Result<(Layer, Layer), String> {
+    let (a, b) = value
+        .and_then(|value| value.split_once(':'))
+        .ok_or_else(|| format!("{} expects a layer pair like {}=monster:ground", name, name))?;
+    Ok((a.parse()?, b.parse()?))
+}
@@ -0,0 +1,99 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use bevy_turborand::rng::{CellState, Rng};
+
+use crate::{loading::AppState, scenario::Scenario};
+
+/// Gameplay time. With a fixed timestep every frame is exactly one tick of `1 / hz` seconds, so
+/// slow frames slow the simulation down instead of changing it.
+pub struct SimulationClock {
+    pub tick: u64,
+    /// Length of the current tick, in seconds.
+    pub dt: f32,
+    pub elapsed: f64,
+    fixed_dt: Option<f32>,
+}
+
+/// Seeded randomness for gameplay systems. Each system draws from its own stream, seeded by the
+/// tick, so the numbers don't depend on the order the executor happens to run systems in.
+pub struct SimulationRng {
+    pub seed: u64,
+}
+
+/// The gameplay systems that draw random numbers, one stream each.
+#[derive(Debug, Clone, Copy)]
+pub enum RngStream {
+    Waves = 1,
+    Projectiles,
+    Hitscan,
+}
+
+impl SimulationRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// A generator for `stream` on `tick`. Systems draw from it at most once per tick.
+    pub fn stream(&self, stream: RngStream, tick: u64) -> Rng<CellState> {
+        Rng::with_seed(splitmix64(
+            self.seed ^ splitmix64(tick ^ ((stream as u64) << 56)),
+        ))
+    }
+}
+
+// Spreads nearby inputs across the whole range, so consecutive ticks get unrelated seeds
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        let scenario = app.world.resource::<Scenario>();
+        let seed = scenario
+            .seed
+            .unwrap_or_else(|| Rng::<CellState>::default().u64(..));
+        let fixed_dt = scenario.fixed_timestep.map(|fixed| 1. / fixed.hz);
+
+        info!("simulation seed: {}", seed);
+
+        app.insert_resource(SimulationClock {
+            tick: 0,
+            dt: 0.,
+            elapsed: 0.,
+            fixed_dt,
+        })
+        .insert_resource(SimulationRng::new(seed))
+        .add_startup_system(configure_physics_timestep)
+        .add_system_to_stage(CoreStage::PreUpdate, advance_clock);
+    }
+}
+
+fn configure_physics_timestep(scenario: Res<Scenario>, mut config: ResMut<RapierConfiguration>) {
+    if let Some(fixed) = scenario.fixed_timestep {
+        config.timestep_mode = TimestepMode::Fixed {
+            dt: 1. / fixed.hz,
+            substeps: fixed.substeps,
+        };
+    }
+}
+
+pub fn advance_clock(
+    mut clock: ResMut<SimulationClock>,
+    state: Res<State<AppState>>,
+    time: Res<Time>,
+) {
+    // Gameplay doesn't start until the assets are in
+    if *state.current() != AppState::Playing {
+        clock.dt = 0.;
+        return;
+    }
+
+    clock.tick += 1;
+    clock.dt = clock.fixed_dt.unwrap_or_else(|| time.delta_seconds());
+    clock.elapsed += clock.dt as f64;
+}
@@ -0,0 +1,106 @@
+//! Summary statistics for frame times, and bootstrap confidence intervals for comparing runs.
+
+use bevy_turborand::rng::{CellState, Rng};
+
+/// The `p`th percentile (0 to 100) of sorted samples, interpolating between neighbours.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+
+    let rank = (p / 100.).clamp(0., 1.) * (sorted.len() - 1) as f64;
+    let low = rank.floor() as usize;
+    let high = rank.ceil() as usize;
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+pub fn median(sorted: &[f64]) -> f64 {
+    percentile(sorted, 50.)
+}
+
+pub fn sorted(samples: impl IntoIterator<Item = f64>) -> Vec<f64> {
+    let mut samples: Vec<f64> = samples.into_iter().collect();
+    samples.sort_by(f64::total_cmp);
+    samples
+}
+
+/// A statistic's change from a baseline to a candidate, in percent of the baseline.
+#[derive(Debug, Clone, Copy)]
+pub struct Delta {
+    pub baseline: f64,
+    pub candidate: f64,
+    pub percent: f64,
+    /// Confidence interval of `percent`.
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Delta {
+    /// The interval doesn't cross zero, so the change is unlikely to be noise.
+    pub fn is_significant(&self) -> bool {
+        self.low > 0. || self.high < 0.
+    }
+}
+
+pub struct Bootstrap {
+    pub resamples: usize,
+    /// Width of the confidence interval, e.g. 0.95.
+    pub confidence: f64,
+    pub seed: u64,
+}
+
+impl Default for Bootstrap {
+    fn default() -> Self {
+        Self {
+            resamples: 1000,
+            confidence: 0.95,
+            seed: 0,
+        }
+    }
+}
+
+impl Bootstrap {
+    /// Compares `statistic` between two sets of sorted samples, resampling both with replacement
+    /// to estimate how much of the difference is noise.
+    pub fn delta(
+        &self,
+        baseline: &[f64],
+        candidate: &[f64],
+        statistic: impl Fn(&[f64]) -> f64,
+    ) -> Delta {
+        let base = statistic(baseline);
+        let cand = statistic(candidate);
+
+        let rng = Rng::<CellState>::with_seed(self.seed);
+        let mut a = Vec::with_capacity(baseline.len());
+        let mut b = Vec::with_capacity(candidate.len());
+        let mut deltas: Vec<f64> = (0..self.resamples)
+            .map(|_| {
+                resample(baseline, &rng, &mut a);
+                resample(candidate, &rng, &mut b);
+                percent_change(statistic(&a), statistic(&b))
+            })
+            .filter(|delta| delta.is_finite())
+            .collect();
+        deltas.sort_by(f64::total_cmp);
+
+        let tail = (1. - self.confidence) / 2. * 100.;
+        Delta {
+            baseline: base,
+            candidate: cand,
+            percent: percent_change(base, cand),
+            low: percentile(&deltas, tail),
+            high: percentile(&deltas, 100. - tail),
+        }
+    }
+}
+
+fn resample(samples: &[f64], rng: &Rng<CellState>, into: &mut Vec<f64>) {
+    into.clear();
+    into.extend((0..samples.len()).map(|_| samples[rng.usize(..samples.len())]));
+    into.sort_by(f64::total_cmp);
+}
+
+fn percent_change(baseline: f64, candidate: f64) -> f64 {
+    (candidate - baseline) / baseline * 100.
+}
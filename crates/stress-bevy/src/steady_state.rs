@@ -0,0 +1,78 @@
+//! Separating steady-state frames from transients: the warmup at the start of a run (shader
+//! compilation, asset loads, allocator growth) and the spike after every wave spawns.
+
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::FrameRecord;
+
+/// A stretch of frames counts as settled once `window` consecutive frame times have a
+/// coefficient of variation (standard deviation over mean) of at most `max_variation`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SteadyState {
+    pub window: usize,
+    pub max_variation: f64,
+}
+
+impl Default for SteadyState {
+    fn default() -> Self {
+        Self {
+            window: 60,
+            max_variation: 0.25,
+        }
+    }
+}
+
+impl SteadyState {
+    /// Marks every frame before the run settles, and before it settles again after each new
+    /// wave, as transient. A stretch that never settles is transient throughout.
+    pub fn mark_transients(&self, frames: &mut [FrameRecord]) {
+        let window = self.window.max(1);
+        let mut start = 0;
+
+        while start < frames.len() {
+            // Each wave starts a new stretch, since spawning disturbs the frame times again
+            let end = frames[start..]
+                .iter()
+                .position(|frame| frame.wave != frames[start].wave)
+                .map_or(frames.len(), |len| start + len);
+
+            let stretch = &mut frames[start..end];
+            let settled = self.first_settled(stretch, window).unwrap_or(stretch.len());
+            for (i, frame) in stretch.iter_mut().enumerate() {
+                frame.transient = i < settled;
+            }
+
+            start = end;
+        }
+    }
+
+    // Start of the first window that's calm enough, using running sums so this stays linear
+    pub(crate) fn first_settled(&self, frames: &[FrameRecord], window: usize) -> Option<usize> {
+        if frames.len() < window {
+            return None;
+        }
+
+        let (mut sum, mut sum_squares) = (0., 0.);
+        for (i, frame) in frames.iter().enumerate() {
+            let ms = frame.frame_time_ms;
+            sum += ms;
+            sum_squares += ms * ms;
+
+            if i >= window {
+                let old = frames[i - window].frame_time_ms;
+                sum -= old;
+                sum_squares -= old * old;
+            }
+
+            if i + 1 >= window {
+                let n = window as f64;
+                let mean = sum / n;
+                let variance = (sum_squares / n - mean * mean).max(0.);
+                if mean > 0. && variance.sqrt() / mean <= self.max_variation {
+                    return Some(i + 1 - window);
+                }
+            }
+        }
+        None
+    }
+}
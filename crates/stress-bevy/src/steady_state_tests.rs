@@ -0,0 +1,90 @@
+//! Checks which frames `SteadyState` marks as warmup or post-wave transients.
+
+use crate::{metrics::FrameRecord, steady_state::SteadyState};
+
+const STEADY: SteadyState = SteadyState {
+    window: 4,
+    max_variation: 0.1,
+};
+
+fn frames(times: &[(u32, f64)]) -> Vec<FrameRecord> {
+    times
+        .iter()
+        .enumerate()
+        .map(|(tick, &(wave, frame_time_ms))| FrameRecord {
+            tick: tick as u64,
+            elapsed: 0.,
+            frame_time_ms,
+            wave,
+            monsters: 0,
+            projectiles: 0,
+            world_hash: 0,
+            memory_bytes: None,
+            systems: Default::default(),
+            transient: false,
+        })
+        .collect()
+}
+
+fn transients(frames: &[FrameRecord]) -> Vec<bool> {
+    frames.iter().map(|frame| frame.transient).collect()
+}
+
+#[test]
+fn calm_run_has_no_transients() {
+    let mut frames = frames(&[(0, 10.); 8]);
+    STEADY.mark_transients(&mut frames);
+    assert_eq!(transients(&frames), [false; 8]);
+}
+
+#[test]
+fn warmup_is_transient_until_a_calm_window() {
+    let mut frames = frames(&[
+        (0, 50.),
+        (0, 5.),
+        (0, 10.),
+        (0, 10.),
+        (0, 10.),
+        (0, 10.),
+        (0, 11.),
+    ]);
+    STEADY.mark_transients(&mut frames);
+    assert_eq!(
+        transients(&frames),
+        [true, true, false, false, false, false, false]
+    );
+}
+
+#[test]
+fn each_wave_settles_again() {
+    let mut frames = frames(&[
+        (0, 10.),
+        (0, 10.),
+        (0, 10.),
+        (0, 10.),
+        (1, 40.),
+        (1, 10.),
+        (1, 10.),
+        (1, 10.),
+        (1, 10.),
+    ]);
+    STEADY.mark_transients(&mut frames);
+    assert_eq!(
+        transients(&frames),
+        [false, false, false, false, true, false, false, false, false]
+    );
+}
+
+#[test]
+fn stretch_shorter_than_the_window_is_transient() {
+    let mut frames = frames(&[(0, 10.), (0, 10.), (0, 10.)]);
+    STEADY.mark_transients(&mut frames);
+    assert_eq!(transients(&frames), [true; 3]);
+}
+
+#[test]
+fn first_settled_finds_the_first_calm_window() {
+    let frames = frames(&[(0, 30.), (0, 10.), (0, 10.), (0, 10.), (0, 10.)]);
+    assert_eq!(STEADY.first_settled(&frames, STEADY.window), Some(1));
+    assert_eq!(STEADY.first_settled(&frames[..4], STEADY.window), None);
+}
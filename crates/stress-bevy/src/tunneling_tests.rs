@@ -0,0 +1,130 @@
+//! Headless checks that fast projectiles register hits on monsters at low frame rates.
+
+use bevy::{asset::AssetPlugin, hierarchy::HierarchyPlugin, prelude::*, transform::TransformPlugin};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    archetypes::Health,
+    collision_layers::{CollisionMatrix, Layer},
+    combat::{detect_projectile_collision, HitDetection, Projectile},
+    pooling::ProjectilePool,
+    waves::Monster,
+    weapons::Damage,
+};
+
+const PROJECTILE_SPEED: f32 = 200.;
+const PROJECTILE_RADIUS: f32 = 0.5;
+const MONSTER_DISTANCE: f32 = 20.;
+const MONSTER_HEALTH: f32 = 100.;
+const DAMAGE: f32 = 25.;
+
+struct Shot {
+    monster: Entity,
+    projectile: Entity,
+}
+
+fn headless_app(dt: f32) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(AssetPlugin);
+    #[cfg(feature = "render")]
+    app.add_asset::<Mesh>();
+    app.add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+        .insert_resource(RapierConfiguration {
+            gravity: Vec3::ZERO,
+            timestep_mode: TimestepMode::Fixed { dt, substeps: 1 },
+            ..default()
+        })
+        .insert_resource(CollisionMatrix::default())
+        .init_resource::<ProjectilePool>()
+        .add_system(detect_projectile_collision);
+    app
+}
+
+// Same monster and projectile colliders as `spawn_monster` and `launch_projectile`
+fn fire(app: &mut App, ccd: bool) -> Shot {
+    let layers = CollisionMatrix::default();
+
+    let monster = app
+        .world
+        .spawn()
+        .insert_bundle(TransformBundle::from(Transform::from_xyz(
+            MONSTER_DISTANCE,
+            0.,
+            0.,
+        )))
+        .insert_bundle((
+            RigidBody::Fixed,
+            Collider::cuboid(1., 3., 1.),
+            Monster,
+            HitDetection,
+            Health(MONSTER_HEALTH),
+        ))
+        .insert_bundle(layers.bundle(Layer::Monster))
+        .id();
+
+    let mut projectile = app.world.spawn();
+    projectile
+        .insert_bundle(TransformBundle::default())
+        .insert_bundle((
+            RigidBody::Dynamic,
+            Velocity {
+                linvel: Vec3::X * PROJECTILE_SPEED,
+                angvel: Vec3::ZERO,
+            },
+            Collider::ball(PROJECTILE_RADIUS),
+            Projectile,
+            Damage(DAMAGE),
+            ActiveEvents::COLLISION_EVENTS,
+        ))
+        .insert_bundle(layers.bundle(Layer::Projectile));
+    if ccd {
+        projectile.insert(Ccd::enabled());
+    }
+    let projectile = projectile.id();
+
+    Shot {
+        monster,
+        projectile,
+    }
+}
+
+/// Steps for one simulated second and reports whether the projectile hit the monster.
+fn hit_registered(dt: f32, ccd: bool) -> bool {
+    let mut app = headless_app(dt);
+    let shot = fire(&mut app, ccd);
+
+    for _ in 0..(1. / dt).ceil() as usize {
+        app.update();
+    }
+
+    let health = app.world.get::<Health>(shot.monster).unwrap().0;
+    let despawned = app.world.get_entity(shot.projectile).is_none();
+    assert_eq!(
+        despawned,
+        health < MONSTER_HEALTH,
+        "projectile despawn and damage disagree"
+    );
+    despawned
+}
+
+#[test]
+fn ccd_hits_at_every_timestep() {
+    for hz in [15., 30., 60., 120., 240.] {
+        assert!(hit_registered(1. / hz, true), "missed at {} Hz with CCD", hz);
+    }
+}
+
+#[test]
+fn hits_without_ccd_at_high_frame_rates() {
+    // 200 u/s moves under 1 unit per step, less than the 3 unit overlap window
+    assert!(hit_registered(1. / 240., false));
+}
+
+#[test]
+fn tunnels_without_ccd_at_low_frame_rates() {
+    // 13.3 units per step jumps straight over the monster at x = 20
+    assert!(!hit_registered(1. / 15., false));
+}
@@ -0,0 +1,420 @@
+use bevy::{asset::LoadState, prelude::*, utils::HashMap};
+
+use crate::{
+    animation::{AnimationHelperSetup, HackyHeightFix},
+    archetypes::{MonsterKind, MonsterRegistry},
+    arena::Ground,
+    assets::{self, validate_asset_manifest, AssetManifest},
+    camera::GameCamera,
+    character::{Character, CharacterAnimations},
+    combat::Projectile,
+    loading::AppState,
+    scenario::{ModelMode, PrimitiveShape, Scenario},
+    waves::Monster,
+    weapons::ProjectileType,
+};
+
+const CROSSHAIR_SIZE: f32 = 32.;
+
+/// Meshes used in primitives mode, and in place of a glTF scene that could not be loaded.
+pub struct PrimitiveModels {
+    pub shape: PrimitiveShape,
+    pub character: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+    /// Sized to each monster archetype's collider, made the first time one is drawn.
+    pub monsters: HashMap<MonsterKind, Handle<Mesh>>,
+}
+
+impl PrimitiveModels {
+    pub fn monster(
+        &mut self,
+        kind: MonsterKind,
+        registry: &MonsterRegistry,
+        meshes: &mut Assets<Mesh>,
+    ) -> Handle<Mesh> {
+        let shape = self.shape;
+        self.monsters
+            .entry(kind)
+            .or_insert_with(|| {
+                let half_extents = registry.kind(kind).archetype.collider.half_extents();
+                meshes.add(primitive_mesh(shape, half_extents))
+            })
+            .clone()
+    }
+}
+
+/// The glTF scene spawned under this entity, so a failed load can be swapped for a placeholder.
+#[derive(Component)]
+pub struct SceneModel(pub Handle<Scene>);
+
+#[derive(Component)]
+struct LoadingScreen;
+
+#[derive(Component)]
+struct LoadingProgressBar;
+
+/// Everything drawn: the meshes, scenes and lights, the camera's view, the crosshair and the
+/// loading screen. Gameplay spawns bodies without any of it, and the models are added here once
+/// they appear, so the simulation is the same whether or not this plugin is in.
+pub struct VisualsPlugin;
+
+impl Plugin for VisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_visuals)
+            // The game camera is spawned in the startup stage
+            .add_startup_system_to_stage(StartupStage::PostStartup, add_camera_view)
+            .add_system_set(
+                SystemSet::on_enter(AppState::Loading).with_system(spawn_loading_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Loading).with_system(update_loading_screen),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Loading).with_system(despawn_loading_screen),
+            )
+            .add_system(replace_failed_scenes.after(validate_asset_manifest))
+            // After the update, so bodies spawned in it are drawn on the frame they appear
+            .add_system_to_stage(CoreStage::PostUpdate, draw_ground)
+            .add_system_to_stage(CoreStage::PostUpdate, draw_characters)
+            .add_system_to_stage(CoreStage::PostUpdate, draw_monsters)
+            .add_system_to_stage(CoreStage::PostUpdate, draw_projectiles)
+            .add_system_to_stage(CoreStage::PostUpdate, hide_parked_projectiles);
+    }
+}
+
+fn setup_visuals(
+    mut commands: Commands,
+    scenario: Res<Scenario>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let shape = match scenario.models {
+        ModelMode::Primitives(shape) => shape,
+        ModelMode::Gltf => PrimitiveShape::Capsule,
+    };
+
+    // Sized to match the character's collider
+    commands.insert_resource(PrimitiveModels {
+        shape,
+        character: meshes.add(primitive_mesh(shape, Vec3::new(2., 9., 2.))),
+        material: materials.add(Color::FUCHSIA.into()),
+        monsters: HashMap::default(),
+    });
+
+    if scenario.uses_gltf() {
+        commands.insert_resource(CharacterAnimations {
+            idle: asset_server.load(assets::CHARACTER_IDLE),
+        });
+    }
+
+    spawn_crosshair(&mut commands, asset_server.load(assets::CROSSHAIR_IMAGE));
+
+    // Directional Light
+    commands.spawn_bundle(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 50000.,
+            color: Color::hex("fef6f0").unwrap(),
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform {
+            translation: Vec3::new(0.0, 2.0, 0.0),
+            rotation: Quat::from_rotation_x(-45.),
+            ..default()
+        },
+        ..default()
+    });
+
+    // Ambient Light
+    commands.insert_resource(AmbientLight {
+        color: Color::hex("606b9f").unwrap(),
+        brightness: 0.3,
+    });
+}
+
+pub fn primitive_mesh(shape: PrimitiveShape, half_extents: Vec3) -> Mesh {
+    match shape {
+        PrimitiveShape::Capsule => Mesh::from(shape::Capsule {
+            radius: half_extents.x,
+            depth: 2. * (half_extents.y - half_extents.x),
+            ..default()
+        }),
+        PrimitiveShape::Cube => Mesh::from(shape::Box::new(
+            2. * half_extents.x,
+            2. * half_extents.y,
+            2. * half_extents.z,
+        )),
+    }
+}
+
+pub fn spawn_crosshair(commands: &mut Commands, image: Handle<Image>) {
+    // Full-screen container so the image is centred whatever the window size
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(ImageBundle {
+                style: Style {
+                    size: Size::new(Val::Px(CROSSHAIR_SIZE), Val::Px(CROSSHAIR_SIZE)),
+                    ..default()
+                },
+                image: UiImage(image),
+                ..default()
+            });
+        });
+}
+
+// The camera's transform is placed from its `LookTransform` every frame, so the bundle's is
+// overwritten before anything is drawn
+fn add_camera_view(
+    mut commands: Commands,
+    mut windows: ResMut<Windows>,
+    cameras: Query<Entity, With<GameCamera>>,
+) {
+    // Lock cursor, when there's a window to lock it in
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_cursor_lock_mode(true);
+        window.set_cursor_visibility(false);
+    }
+
+    for camera in cameras.iter() {
+        commands
+            .entity(camera)
+            .insert_bundle(PerspectiveCameraBundle::default());
+    }
+
+    // UI Camera
+    commands.spawn_bundle(UiCameraBundle::default());
+}
+
+fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Percent(25.),
+                    bottom: Val::Percent(10.),
+                    ..default()
+                },
+                size: Size::new(Val::Percent(50.), Val::Px(12.)),
+                ..default()
+            },
+            color: Color::rgba(0., 0., 0., 0.6).into(),
+            ..default()
+        })
+        .insert(LoadingScreen)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                        ..default()
+                    },
+                    color: Color::hex("43bc68").unwrap().into(),
+                    ..default()
+                })
+                .insert(LoadingProgressBar);
+        });
+}
+
+fn update_loading_screen(
+    manifest: Res<AssetManifest>,
+    mut bars: Query<&mut Style, With<LoadingProgressBar>>,
+) {
+    let total = manifest.entries.len();
+    let settled = manifest.entries.iter().filter(|e| e.is_settled()).count();
+    for mut style in bars.iter_mut() {
+        style.size.width = Val::Percent(100. * settled as f32 / total.max(1) as f32);
+    }
+}
+
+fn despawn_loading_screen(mut commands: Commands, screens: Query<Entity, With<LoadingScreen>>) {
+    for screen in screens.iter() {
+        commands.entity(screen).despawn_recursive();
+    }
+}
+
+fn draw_ground(
+    mut commands: Commands,
+    scenario: Res<Scenario>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    grounds: Query<Entity, Added<Ground>>,
+) {
+    for ground in grounds.iter() {
+        commands.entity(ground).insert_bundle((
+            meshes.add(Mesh::from(shape::Plane {
+                size: scenario.arena_size,
+            })),
+            materials.add(Color::hex("43bc68").unwrap().into()),
+            Visibility::default(),
+            ComputedVisibility::default(),
+        ));
+    }
+}
+
+fn draw_characters(
+    mut commands: Commands,
+    scenario: Res<Scenario>,
+    asset_server: Res<AssetServer>,
+    primitives: Res<PrimitiveModels>,
+    characters: Query<Entity, Added<Character>>,
+) {
+    for character in characters.iter() {
+        if scenario.uses_gltf() {
+            let my_gltf = asset_server.load(assets::CHARACTER_SCENE);
+            commands
+                .entity(character)
+                .with_children(|parent| {
+                    parent.spawn_scene(my_gltf.clone());
+                })
+                .insert_bundle((SceneModel(my_gltf), AnimationHelperSetup, HackyHeightFix));
+        } else {
+            commands.entity(character).with_children(|parent| {
+                parent.spawn_bundle(PbrBundle {
+                    mesh: primitives.character.clone(),
+                    material: primitives.material.clone(),
+                    ..default()
+                });
+            });
+        }
+    }
+}
+
+fn draw_monsters(
+    mut commands: Commands,
+    scenario: Res<Scenario>,
+    registry: Option<Res<MonsterRegistry>>,
+    mut primitives: ResMut<PrimitiveModels>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    monsters: Query<(Entity, &MonsterKind), Added<Monster>>,
+) {
+    // Monsters only spawn once it's built
+    let registry = match registry {
+        Some(x) => x,
+        None => return,
+    };
+
+    for (monster, &kind) in monsters.iter() {
+        if scenario.uses_gltf() {
+            let my_gltf = registry.kind(kind).scene.clone();
+            commands
+                .entity(monster)
+                .with_children(|parent| {
+                    parent.spawn_scene(my_gltf.clone());
+                })
+                .insert_bundle((SceneModel(my_gltf), AnimationHelperSetup, HackyHeightFix));
+        } else {
+            let mesh = primitives.monster(kind, &registry, &mut meshes);
+            let material = primitives.material.clone();
+            commands.entity(monster).with_children(|parent| {
+                parent.spawn_bundle(PbrBundle {
+                    mesh,
+                    material,
+                    ..default()
+                });
+            });
+        }
+    }
+}
+
+// Meshes and materials are shared between shots so bullet-hell weapons don't allocate them per
+// projectile. Pooled projectiles are drawn again each time they're fired.
+fn draw_projectiles(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut projectile_meshes: Local<HashMap<u32, Handle<Mesh>>>,
+    mut projectile_materials: Local<HashMap<[u32; 4], Handle<StandardMaterial>>>,
+    projectiles: Query<(Entity, &ProjectileType), Added<Projectile>>,
+) {
+    for (entity, projectile) in projectiles.iter() {
+        let mesh = projectile_meshes
+            .entry(projectile.radius.to_bits())
+            .or_insert_with(|| {
+                meshes.add(Mesh::from(shape::Icosphere {
+                    radius: projectile.radius,
+                    ..default()
+                }))
+            })
+            .clone();
+        let material = projectile_materials
+            .entry(projectile.color.as_rgba_f32().map(f32::to_bits))
+            .or_insert_with(|| materials.add(projectile.color.into()))
+            .clone();
+
+        commands.entity(entity).insert_bundle((
+            mesh,
+            material,
+            Visibility::default(),
+            ComputedVisibility::default(),
+        ));
+    }
+}
+
+// Parked projectiles keep their `ProjectileType` but lose the `Projectile` marker
+fn hide_parked_projectiles(
+    mut parked: Query<&mut Visibility, (With<ProjectileType>, Without<Projectile>)>,
+) {
+    for mut visibility in parked.iter_mut() {
+        if visibility.is_visible {
+            visibility.is_visible = false;
+        }
+    }
+}
+
+// Swap scenes that will never load for a capsule, so `AnimationHelperSetup` doesn't wait forever.
+#[allow(clippy::too_many_arguments)]
+fn replace_failed_scenes(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut placeholders: ResMut<PrimitiveModels>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    registry: Option<Res<MonsterRegistry>>,
+    hosts: Query<(Entity, &SceneModel)>,
+    monsters: Query<&MonsterKind>,
+    characters: Query<&Character>,
+) {
+    for (host, SceneModel(scene)) in hosts.iter() {
+        match asset_server.get_load_state(scene) {
+            LoadState::Failed => {}
+            LoadState::Loaded => {
+                commands.entity(host).remove::<SceneModel>();
+                continue;
+            }
+            _ => continue,
+        }
+
+        let mesh = if let (Ok(&kind), Some(registry)) = (monsters.get(host), &registry) {
+            placeholders.monster(kind, registry, &mut meshes)
+        } else if characters.get(host).is_ok() {
+            placeholders.character.clone()
+        } else {
+            continue;
+        };
+
+        commands
+            .entity(host)
+            .remove::<SceneModel>()
+            .remove::<AnimationHelperSetup>()
+            .remove::<HackyHeightFix>()
+            .with_children(|parent| {
+                parent.spawn_bundle(PbrBundle {
+                    mesh,
+                    material: placeholders.material.clone(),
+                    ..default()
+                });
+            });
+    }
+}
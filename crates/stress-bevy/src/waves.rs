@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    archetypes::{Health, MonsterKind, MonsterRegistry, MoveSpeed},
+    character::Character,
+    collision_layers::{CollisionMatrix, Layer},
+    combat::HitDetection,
+    loading::AppState,
+    scenario::Scenario,
+    simulation::{RngStream, SimulationClock, SimulationRng},
+};
+
+const WAVE_DELAY_SECONDS: f32 = 3.;
+/// How quickly a monster's walk catches up with its heading, per second. Leaves room for knockback.
+const MONSTER_STEERING: f32 = 4.;
+
+#[derive(Component)]
+pub struct Monster;
+
+/// Seconds since the last wave, starting full so the first wave spawns immediately.
+struct WaveTimer(f32);
+
+impl Default for WaveTimer {
+    fn default() -> Self {
+        Self(WAVE_DELAY_SECONDS)
+    }
+}
+
+/// Waves spawned so far.
+#[derive(Default)]
+pub struct Wave(pub u32);
+
+/// A monster as soon as play starts, then a wave of them every few seconds across the arena, all
+/// walking toward the character.
+pub struct WavesPlugin;
+
+impl Plugin for WavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WaveTimer>()
+            .init_resource::<Wave>()
+            .add_system_set(SystemSet::on_enter(AppState::Playing).with_system(spawn_first_monster))
+            .add_system_set(
+                SystemSet::on_update(AppState::Playing)
+                    .with_system(spawn_waves)
+                    .with_system(chase_character),
+            );
+    }
+}
+
+// Monster archetypes aren't known until the registry is built, so the first one waits for play.
+fn spawn_first_monster(
+    mut commands: Commands,
+    layers: Res<CollisionMatrix>,
+    registry: Res<MonsterRegistry>,
+) {
+    spawn_monster(
+        Vec3::new(2., 2., 2.),
+        MonsterKind(0),
+        &mut commands,
+        &registry,
+        &layers,
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_waves(
+    mut commands: Commands,
+    scenario: Res<Scenario>,
+    layers: Res<CollisionMatrix>,
+    registry: Res<MonsterRegistry>,
+    clock: Res<SimulationClock>,
+    mut wave_timer: ResMut<WaveTimer>,
+    mut wave: ResMut<Wave>,
+    sim_rng: Res<SimulationRng>,
+) {
+    if matches!(scenario.waves, Some(waves) if wave.0 >= waves) {
+        return;
+    }
+
+    wave_timer.0 += clock.dt;
+
+    if wave_timer.0 >= WAVE_DELAY_SECONDS {
+        let rng = sim_rng.stream(RngStream::Waves, clock.tick);
+        wave_timer.0 = 0.;
+        wave.0 += 1;
+        let arena_half = scenario.arena_size / 2.;
+        let mut temp_spawn_loc: Vec3 = Vec3::ZERO;
+
+        for _ in 0..scenario.monsters_per_wave {
+            temp_spawn_loc.x = rng.f32_normalized() * arena_half;
+            temp_spawn_loc.y = 1.;
+            temp_spawn_loc.z = rng.f32_normalized() * arena_half;
+            let kind = registry.choose(&rng);
+            spawn_monster(temp_spawn_loc, kind, &mut commands, &registry, &layers);
+        }
+    }
+}
+
+pub fn spawn_monster(
+    spawn_loc: Vec3,
+    kind: MonsterKind,
+    commands: &mut Commands,
+    registry: &MonsterRegistry,
+    layers: &CollisionMatrix,
+) {
+    let archetype = &registry.kind(kind).archetype;
+
+    // Just the body, the model is added by `VisualsPlugin` when there's one to draw
+    commands
+        .spawn_bundle(TransformBundle::from(Transform::from_xyz(
+            spawn_loc.x,
+            spawn_loc.y,
+            spawn_loc.z,
+        )))
+        .insert_bundle((
+            RigidBody::Dynamic,
+            Velocity::default(),
+            GravityScale(archetype.gravity_scale),
+            archetype.collider.collider(),
+            ColliderMassProperties::Density(archetype.mass / archetype.collider.volume()),
+            Monster,
+            kind,
+            Health(archetype.health),
+            MoveSpeed(archetype.speed),
+            HitDetection,
+        ))
+        .insert_bundle(layers.bundle(Layer::Monster))
+        .insert(LockedAxes::ROTATION_LOCKED_X | LockedAxes::ROTATION_LOCKED_Z)
+        .insert(Damping {
+            linear_damping: archetype.linear_damping,
+            angular_damping: archetype.angular_damping,
+        });
+}
+
+// Steers the horizontal velocity, leaving the vertical to gravity
+fn chase_character(
+    character: Query<&Transform, With<Character>>,
+    mut monsters: Query<(&Transform, &MoveSpeed, &mut Velocity), With<Monster>>,
+    clock: Res<SimulationClock>,
+) {
+    let character = match character.get_single() {
+        Ok(x) => x,
+        _ => return,
+    };
+    let steering = (MONSTER_STEERING * clock.dt).min(1.);
+
+    for (transform, MoveSpeed(speed), mut velocity) in monsters.iter_mut() {
+        let heading = (character.translation - transform.translation) * Vec3::new(1., 0., 1.);
+        let walk = heading.normalize_or_zero() * *speed;
+        let horizontal = velocity.linvel * Vec3::new(1., 0., 1.);
+        velocity.linvel += (walk - horizontal) * steering;
+    }
+}
@@ -0,0 +1,279 @@
+use bevy::prelude::*;
+use bevy_turborand::rng::{CellState, Rng};
+
+use crate::archetypes::Health;
+
+const WEAPON_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// A physical projectile, simulated as a rapier rigid body. Fired projectiles carry their type,
+/// so they can be drawn.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ProjectileType {
+    pub speed: f32,
+    pub radius: f32,
+    pub damage: f32,
+    #[cfg(feature = "render")]
+    pub color: Color,
+    /// Explode on the first collision instead of dealing direct damage.
+    pub explosion: Option<Explosion>,
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Explosion {
+    pub radius: f32,
+    /// Damage at the centre, falling off linearly to zero at `radius`.
+    pub damage: f32,
+    /// Impulse at the centre, with the same falloff as damage.
+    pub impulse: f32,
+}
+
+/// An instant hit, resolved with a ray cast.
+#[derive(Debug, Clone, Copy)]
+pub struct HitscanType {
+    pub damage: f32,
+    pub range: f32,
+    /// How many targets a ray passes through before it stops.
+    pub pierce: u32,
+}
+
+/// What a weapon fires.
+#[derive(Debug, Clone, Copy)]
+pub enum WeaponKind {
+    Projectile(ProjectileType),
+    Hitscan(HitscanType),
+}
+
+#[derive(Debug, Clone)]
+pub struct Weapon {
+    pub name: &'static str,
+    /// Shots per second.
+    pub fire_rate: f32,
+    /// Keep firing while the trigger is held.
+    pub automatic: bool,
+    /// Projectiles or rays per shot.
+    pub projectiles_per_shot: u32,
+    /// Half-angle of the spread cone, in radians.
+    pub spread: f32,
+    pub kind: WeaponKind,
+}
+
+impl Weapon {
+    /// A random direction inside this weapon's spread cone around `aim`.
+    pub fn spread_direction(&self, aim: Vec3, rng: &Rng<CellState>) -> Vec3 {
+        if self.spread <= 0. {
+            return aim;
+        }
+
+        let (u, v) = aim.any_orthonormal_pair();
+        let angle = rng.f32() * std::f32::consts::TAU;
+        let offset = self.spread.tan() * rng.f32().sqrt();
+
+        (aim + (u * angle.cos() + v * angle.sin()) * offset).normalize()
+    }
+}
+
+/// The weapons a character carries, switched with the number keys.
+#[derive(Component)]
+pub struct Loadout {
+    pub weapons: Vec<Weapon>,
+    pub current: usize,
+    pub last_shot: f64,
+}
+
+impl Loadout {
+    pub fn weapon(&self) -> &Weapon {
+        &self.weapons[self.current]
+    }
+
+    /// Whether the current weapon fires this frame. Starts the cooldown if it does.
+    pub fn try_fire(&mut self, mouse_button: &Input<MouseButton>, now: f64) -> bool {
+        let weapon = self.weapon();
+        let triggered = if weapon.automatic {
+            mouse_button.pressed(MouseButton::Left)
+        } else {
+            mouse_button.just_pressed(MouseButton::Left)
+        };
+
+        if !triggered || now - self.last_shot < 1. / weapon.fire_rate as f64 {
+            return false;
+        }
+
+        self.last_shot = now;
+        true
+    }
+}
+
+impl Default for Loadout {
+    fn default() -> Self {
+        Self {
+            weapons: vec![
+                Weapon {
+                    name: "pistol",
+                    fire_rate: 4.,
+                    automatic: false,
+                    projectiles_per_shot: 1,
+                    spread: 0.,
+                    kind: WeaponKind::Projectile(ProjectileType {
+                        speed: 200.,
+                        radius: 0.5,
+                        damage: 25.,
+                        #[cfg(feature = "render")]
+                        color: Color::DARK_GREEN,
+                        explosion: None,
+                    }),
+                },
+                Weapon {
+                    name: "rifle",
+                    fire_rate: 10.,
+                    automatic: true,
+                    projectiles_per_shot: 1,
+                    spread: 0.02,
+                    kind: WeaponKind::Projectile(ProjectileType {
+                        speed: 250.,
+                        radius: 0.3,
+                        damage: 20.,
+                        #[cfg(feature = "render")]
+                        color: Color::YELLOW,
+                        explosion: None,
+                    }),
+                },
+                Weapon {
+                    name: "shotgun",
+                    fire_rate: 1.5,
+                    automatic: false,
+                    projectiles_per_shot: 12,
+                    spread: 0.12,
+                    kind: WeaponKind::Projectile(ProjectileType {
+                        speed: 180.,
+                        radius: 0.25,
+                        damage: 10.,
+                        #[cfg(feature = "render")]
+                        color: Color::ORANGE,
+                        explosion: None,
+                    }),
+                },
+                Weapon {
+                    name: "bullet hose",
+                    fire_rate: 30.,
+                    automatic: true,
+                    projectiles_per_shot: 8,
+                    spread: 0.25,
+                    kind: WeaponKind::Projectile(ProjectileType {
+                        speed: 150.,
+                        radius: 0.4,
+                        damage: 5.,
+                        #[cfg(feature = "render")]
+                        color: Color::PURPLE,
+                        explosion: None,
+                    }),
+                },
+                Weapon {
+                    name: "rocket launcher",
+                    fire_rate: 1.2,
+                    automatic: false,
+                    projectiles_per_shot: 1,
+                    spread: 0.,
+                    kind: WeaponKind::Projectile(ProjectileType {
+                        speed: 80.,
+                        radius: 0.6,
+                        damage: 0.,
+                        #[cfg(feature = "render")]
+                        color: Color::RED,
+                        explosion: Some(Explosion {
+                            radius: 12.,
+                            damage: 120.,
+                            impulse: 800.,
+                        }),
+                    }),
+                },
+                Weapon {
+                    name: "grenade spam",
+                    fire_rate: 8.,
+                    automatic: true,
+                    projectiles_per_shot: 3,
+                    spread: 0.15,
+                    kind: WeaponKind::Projectile(ProjectileType {
+                        speed: 60.,
+                        radius: 0.4,
+                        damage: 0.,
+                        #[cfg(feature = "render")]
+                        color: Color::MAROON,
+                        explosion: Some(Explosion {
+                            radius: 6.,
+                            damage: 40.,
+                            impulse: 300.,
+                        }),
+                    }),
+                },
+                Weapon {
+                    name: "railgun",
+                    fire_rate: 1.,
+                    automatic: false,
+                    projectiles_per_shot: 1,
+                    spread: 0.,
+                    kind: WeaponKind::Hitscan(HitscanType {
+                        damage: 150.,
+                        range: 500.,
+                        pierce: 4,
+                    }),
+                },
+                Weapon {
+                    name: "laser minigun",
+                    fire_rate: 40.,
+                    automatic: true,
+                    projectiles_per_shot: 4,
+                    spread: 0.05,
+                    kind: WeaponKind::Hitscan(HitscanType {
+                        damage: 8.,
+                        range: 300.,
+                        pierce: 0,
+                    }),
+                },
+            ],
+            current: 0,
+            last_shot: f64::NEG_INFINITY,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Damage(pub f32);
+
+/// Takes `damage` off a target's health and despawns it once it runs out.
+pub fn apply_damage(commands: &mut Commands, target: Entity, health: &mut Health, damage: f32) {
+    // Several hits can land in one frame, only despawn once
+    if health.0 <= 0. {
+        return;
+    }
+
+    health.0 -= damage;
+    if health.0 <= 0. {
+        commands.entity(target).despawn_recursive();
+    }
+}
+
+pub fn switch_weapon(keyboard: Res<Input<KeyCode>>, mut loadouts: Query<&mut Loadout>) {
+    for mut loadout in loadouts.iter_mut() {
+        let count = loadout.weapons.len();
+        if let Some(index) = WEAPON_KEYS
+            .iter()
+            .take(count)
+            .position(|&key| keyboard.just_pressed(key))
+        {
+            if index != loadout.current {
+                loadout.current = index;
+                info!("switched to {}", loadout.weapon().name);
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Checks the median frame time of the game's plugins, run headless at fixed monster counts,
+//! against the budgets in `perf_budgets.ron`.
+//!
+//! Only meaningful on optimized builds, so debug builds skip it: `cargo test --release`.
+
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use bevy::{log::LogPlugin, prelude::*};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use stress_bevy::{
+    headless::HeadlessPlugins,
+    loading::AppState,
+    metrics::Environment,
+    recorder::MetricsRecorder,
+    scenario::{FixedTimestep, Scenario},
+    simulation::SimulationClock,
+    stats,
+    steady_state::SteadyState,
+    GamePlugins,
+};
+
+const LOADING_TIMEOUT: Duration = Duration::from_secs(60);
+/// Re-measures every median and writes them, and this machine, back to `perf_budgets.ron`.
+const UPDATE_VAR: &str = "UPDATE_PERF_BUDGETS";
+
+#[derive(Serialize, Deserialize)]
+struct Baseline {
+    /// Simulation ticks per run, the first of which are warmup.
+    ticks: u64,
+    /// How much slower than the measured median a run may be, for noise.
+    margin: f64,
+    /// What the medians were measured on, if they have been.
+    machine: Option<String>,
+    budgets: Vec<Budget>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Budget {
+    monsters: u32,
+    median_ms: Option<f64>,
+}
+
+// One test running every count in turn, since runs in parallel would slow each other down
+#[test]
+fn median_frame_time_within_budget() {
+    if cfg!(debug_assertions) {
+        eprintln!("warning: skipping frame time budgets on a debug build, use --release");
+        return;
+    }
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/perf_budgets.ron");
+    let text = std::fs::read_to_string(&path).unwrap();
+    let mut baseline: Baseline = ron::from_str(&text)
+        .unwrap_or_else(|err| panic!("failed to parse {}: {}", path.display(), err));
+
+    let machine = describe_machine();
+    let update = std::env::var_os(UPDATE_VAR).is_some();
+    if !update {
+        match &baseline.machine {
+            None => {
+                eprintln!(
+                    "warning: no medians measured in {}, record them with {}=1",
+                    path.display(),
+                    UPDATE_VAR
+                );
+                return;
+            }
+            Some(measured_on) if *measured_on != machine => eprintln!(
+                "warning: budgets were measured on {}, not on this {}",
+                measured_on, machine
+            ),
+            Some(_) => {}
+        }
+    }
+
+    let mut over = Vec::new();
+    for budget in baseline.budgets.iter_mut() {
+        let median = median_frame_time(budget.monsters, baseline.ticks);
+        if update {
+            println!("{:>5} monsters: median {:.2} ms", budget.monsters, median);
+            budget.median_ms = Some(median);
+            continue;
+        }
+
+        let limit = match budget.median_ms {
+            Some(measured) => measured * baseline.margin,
+            None => {
+                eprintln!(
+                    "warning: no median measured for {} monsters",
+                    budget.monsters
+                );
+                continue;
+            }
+        };
+        println!(
+            "{:>5} monsters: median {:.2} ms, budget {:.2} ms",
+            budget.monsters, median, limit
+        );
+        if median > limit {
+            over.push(format!(
+                "{} monsters: {:.2} ms over the {:.2} ms budget",
+                budget.monsters, median, limit
+            ));
+        }
+    }
+
+    if update {
+        baseline.machine = Some(machine);
+        // Keep the explanation at the top of the file
+        let header: String = text
+            .lines()
+            .take_while(|line| line.starts_with("//"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let body = ron::ser::to_string_pretty(&baseline, PrettyConfig::new()).unwrap();
+        std::fs::write(&path, format!("{}{}\n", header, body)).unwrap();
+        println!("wrote {}", path.display());
+    }
+
+    assert!(over.is_empty(), "{}", over.join("\n"));
+}
+
+// The hardware the medians depend on, leaving out the build and the commit
+fn describe_machine() -> String {
+    let environment = Environment::detect(String::new());
+    format!(
+        "{}, {} cores, {} threads, {}",
+        environment.cpu.as_deref().unwrap_or("unknown cpu"),
+        environment
+            .cores
+            .map_or("?".to_string(), |cores| cores.to_string()),
+        environment
+            .threads
+            .map_or("?".to_string(), |threads| threads.to_string()),
+        environment.os
+    )
+}
+
+// A single wave of `monsters`, with nobody playing so none of them die
+fn median_frame_time(monsters: u32, ticks: u64) -> f64 {
+    let scenario = Scenario {
+        fixed_timestep: Some(FixedTimestep::default()),
+        seed: Some(1),
+        waves: Some(1),
+        monsters_per_wave: monsters,
+        ..default()
+    };
+
+    // The log subscriber is global, and only the first app could install it
+    let mut app = App::new();
+    app.insert_resource(scenario.collision.clone())
+        .insert_resource(scenario)
+        .add_plugins_with(HeadlessPlugins, |group| group.disable::<LogPlugin>())
+        .add_plugins(GamePlugins);
+
+    let started = Instant::now();
+    while *app.world.resource::<State<AppState>>().current() != AppState::Playing {
+        assert!(
+            started.elapsed() < LOADING_TIMEOUT,
+            "assets didn't finish loading"
+        );
+        app.update();
+    }
+    while app.world.resource::<SimulationClock>().tick < ticks {
+        app.update();
+    }
+
+    let mut frames = app.world.resource::<MetricsRecorder>().file.frames.clone();
+    SteadyState::default().mark_transients(&mut frames);
+
+    let mut samples = stats::sorted(
+        frames
+            .iter()
+            .filter(|frame| !frame.transient)
+            .map(|frame| frame.frame_time_ms),
+    );
+    if samples.is_empty() {
+        eprintln!(
+            "warning: {} monsters never settled, using every frame",
+            monsters
+        );
+        samples = stats::sorted(frames.iter().map(|frame| frame.frame_time_ms));
+    }
+    assert!(!samples.is_empty(), "no frames were recorded");
+    stats::median(&samples)
+}